@@ -0,0 +1,103 @@
+use reqwest::{header::HeaderValue, Client, Request, Response};
+
+use crate::{
+    digest_auth::DigestAuth,
+    error::{FetchError, FetchResult, OAuth2Error},
+    oauth2::{AccessToken, ClientCredentials, RefreshTokenGrant},
+};
+
+/// Authentication applied by `Fetch` to every outgoing request
+#[derive(Debug, Clone)]
+pub enum FetchAuth {
+    /// Sends a bearer token obtained with the OAuth2 client credentials grant.
+    /// The token is refreshed once and the request re-sent if the server answers `401`
+    ClientCredentials(ClientCredentials),
+    /// Sends a bearer token kept fresh with the OAuth2 refresh token grant, e.g. after a
+    /// `DeviceAuthorization` flow. Rotated tokens are handed to the grant's `TokenStore`
+    RefreshToken(RefreshTokenGrant),
+    /// Answers `401 WWW-Authenticate: Digest` challenges (RFC 7616) and re-sends the request
+    Digest(DigestAuth),
+}
+
+/// What the auth layer attached to a request, used to decide how to react to a `401`
+pub(crate) enum Authorization {
+    None,
+    Bearer(String),
+    Digest,
+}
+
+impl FetchAuth {
+    pub(crate) async fn authorize(
+        &self,
+        client: &Client,
+        request: &mut Request,
+    ) -> FetchResult<Authorization> {
+        match self {
+            FetchAuth::ClientCredentials(credentials) => {
+                let token = credentials.access_token(client).await?;
+                set_bearer(request, &token)?;
+                Ok(Authorization::Bearer(token.access_token))
+            }
+            FetchAuth::RefreshToken(grant) => {
+                let token = grant.access_token(client).await?;
+                set_bearer(request, &token)?;
+                Ok(Authorization::Bearer(token.access_token))
+            }
+            FetchAuth::Digest(digest) => match digest.authorize(request)? {
+                true => Ok(Authorization::Digest),
+                false => Ok(Authorization::None),
+            },
+        }
+    }
+
+    /// Identifies whose credentials are sent, so responses for different users are cached apart
+    pub(crate) fn identity(&self) -> String {
+        match self {
+            FetchAuth::ClientCredentials(credentials) => credentials.identity(),
+            FetchAuth::RefreshToken(grant) => grant.identity(),
+            FetchAuth::Digest(digest) => digest.identity(),
+        }
+    }
+
+    /// Called when the server answered `401`. Returns true if the request should be authorized
+    /// again and re-sent
+    pub(crate) async fn unauthorized(
+        &self,
+        authorization: &Authorization,
+        response: &Response,
+    ) -> bool {
+        match (self, authorization) {
+            (FetchAuth::ClientCredentials(credentials), Authorization::Bearer(token)) => {
+                credentials.invalidate(token).await;
+                true
+            }
+            (FetchAuth::RefreshToken(grant), Authorization::Bearer(token)) => {
+                grant.invalidate(token).await;
+                true
+            }
+            (FetchAuth::Digest(digest), _) => digest.challenge(response),
+            _ => false,
+        }
+    }
+}
+
+/// Sends `token` in the `Authorization` header. Other token types than `Bearer` (e.g. `DPoP`)
+/// need proof of possession, which is not supported, so they are rejected
+fn set_bearer(request: &mut Request, token: &AccessToken) -> FetchResult<()> {
+    if !token.token_type.eq_ignore_ascii_case("bearer") {
+        return Err(FetchError::OAuth2(OAuth2Error::UnsupportedTokenType(
+            token.token_type.clone(),
+        )));
+    }
+    let header =
+        HeaderValue::from_str(&format!("Bearer {}", token.access_token)).map_err(|_| {
+            FetchError::HeaderParseError(
+                reqwest::header::AUTHORIZATION.to_string(),
+                "Bearer <redacted>".to_string(),
+            )
+        })?;
+    request
+        .headers_mut()
+        .insert(reqwest::header::AUTHORIZATION, header);
+    Ok(())
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use reqwest::Request;
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::Instant,
+};
+
+use crate::{
+    error::{FetchError, FetchResult},
+    utils::glob_match,
+};
+
+/// Which requests share a bulkhead compartment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BulkheadKey {
+    /// Every host gets its own compartment
+    Host,
+    /// Requests whose path matches one of the patterns (`*` matches anything) get a compartment
+    /// per pattern. Requests matching none of them are not limited
+    Endpoint(Vec<String>),
+}
+
+#[derive(Debug)]
+struct Compartment {
+    semaphore: Arc<Semaphore>,
+    queued: AtomicUsize,
+}
+
+/// Decrements the queue length when a queued request gets its turn or is dropped
+struct Queued<'a>(&'a AtomicUsize);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Limits the number of concurrent calls per host or endpoint, so one slow upstream cannot tie
+/// up the whole application.
+///
+/// Up to `max_concurrent` calls run at once; up to `max_queue` more wait for a free slot. Calls
+/// beyond that are rejected with `FetchError::BulkheadFull`. How long a call waited is reported
+/// in `FetchResponse::queue_wait`.
+///
+/// Clones share their compartments.
+///
+/// # Example
+/// ```rust
+/// use rust_fetch::{Bulkhead, BulkheadKey, Fetch, FetchConfig};
+///
+/// let client = Fetch::new(
+///     "https://reports.local",
+///     Some(FetchConfig {
+///         bulkhead: Some(
+///             Bulkhead::new(4, 16).with_key(BulkheadKey::Endpoint(vec!["/export/*".to_string()])),
+///         ),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Bulkhead {
+    max_concurrent: usize,
+    max_queue: usize,
+    key: BulkheadKey,
+    compartments: Arc<Mutex<HashMap<String, Arc<Compartment>>>>,
+}
+
+impl Bulkhead {
+    /// A bulkhead per host allowing `max_concurrent` calls in flight and `max_queue` waiting
+    pub fn new(max_concurrent: usize, max_queue: usize) -> Self {
+        Self {
+            max_concurrent: max_concurrent.max(1),
+            max_queue,
+            key: BulkheadKey::Host,
+            compartments: Default::default(),
+        }
+    }
+
+    pub fn with_key(mut self, key: BulkheadKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    fn compartment_key(&self, request: &Request) -> Option<String> {
+        match &self.key {
+            BulkheadKey::Host => Some(request.url().host_str().unwrap_or_default().to_string()),
+            BulkheadKey::Endpoint(patterns) => patterns
+                .iter()
+                .find(|pattern| glob_match(pattern, request.url().path()))
+                .cloned(),
+        }
+    }
+
+    /// Waits for a slot for `request`. Returns the permit to hold while the call is in flight and
+    /// how long the call was queued, or `None` if the request is not limited
+    pub(crate) async fn acquire(
+        &self,
+        request: &Request,
+    ) -> FetchResult<Option<(OwnedSemaphorePermit, Duration)>> {
+        let Some(key) = self.compartment_key(request) else {
+            return Ok(None);
+        };
+        let compartment = self.compartment(&key);
+
+        if let Ok(permit) = compartment.semaphore.clone().try_acquire_owned() {
+            return Ok(Some((permit, Duration::ZERO)));
+        }
+        if compartment.queued.fetch_add(1, Ordering::SeqCst) >= self.max_queue {
+            compartment.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err(FetchError::BulkheadFull { key });
+        }
+
+        let _queued = Queued(&compartment.queued);
+        let started = Instant::now();
+        let permit = compartment
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("bulkhead semaphores are never closed");
+        Ok(Some((permit, started.elapsed())))
+    }
+
+    /// Takes a free slot for `request` without queueing. Returns `None` if the request is not
+    /// limited, or `FetchError::BulkheadFull` if every slot is taken
+    pub(crate) fn try_acquire(
+        &self,
+        request: &Request,
+    ) -> FetchResult<Option<OwnedSemaphorePermit>> {
+        let Some(key) = self.compartment_key(request) else {
+            return Ok(None);
+        };
+        match self.compartment(&key).semaphore.clone().try_acquire_owned() {
+            Ok(permit) => Ok(Some(permit)),
+            Err(_) => Err(FetchError::BulkheadFull { key }),
+        }
+    }
+
+    fn compartment(&self, key: &str) -> Arc<Compartment> {
+        self.compartments
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert_with(|| {
+                Arc::new(Compartment {
+                    semaphore: Arc::new(Semaphore::new(self.max_concurrent)),
+                    queued: AtomicUsize::new(0),
+                })
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{Method, Request, Url};
+
+    use super::{Bulkhead, BulkheadKey};
+    use crate::FetchError;
+
+    fn request(url: &str) -> Request {
+        Request::new(Method::GET, Url::parse(url).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_queue_overflow_is_rejected() {
+        let bulkhead = Bulkhead::new(1, 0);
+        let held = bulkhead
+            .acquire(&request("http://a.local/x"))
+            .await
+            .unwrap();
+        assert!(held.is_some());
+
+        match bulkhead.acquire(&request("http://a.local/y")).await {
+            Err(FetchError::BulkheadFull { key }) => assert_eq!("a.local", key),
+            other => panic!("expected a full bulkhead, got {other:?}"),
+        }
+        assert!(bulkhead.acquire(&request("http://b.local/x")).await.is_ok());
+
+        drop(held);
+        assert!(bulkhead.acquire(&request("http://a.local/y")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_endpoints_are_not_limited() {
+        let bulkhead =
+            Bulkhead::new(1, 0).with_key(BulkheadKey::Endpoint(vec!["/export/*".to_string()]));
+        let _held = bulkhead
+            .acquire(&request("http://a.local/export/1"))
+            .await
+            .unwrap();
+
+        assert!(bulkhead
+            .acquire(&request("http://a.local/export/2"))
+            .await
+            .is_err());
+        assert!(bulkhead
+            .acquire(&request("http://a.local/health"))
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
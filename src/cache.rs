@@ -0,0 +1,585 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Method, Request, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+/// Where the response in a `FetchResponse` came from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// Fetched from the server (also used when no cache is configured)
+    #[default]
+    Network,
+    /// A fresh cached response, served without contacting the server
+    Hit,
+    /// A cached response the server confirmed with `304 Not Modified`
+    Revalidated,
+    /// A stale cached response, served because of `stale-while-revalidate`, `stale-if-error` or
+    /// the offline fallback
+    Stale,
+}
+
+/// A response stored by the HTTP cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub status: u16,
+    /// Response headers, in the order they were received
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// When the response was received (or last revalidated)
+    pub stored_at: SystemTime,
+    /// The request header values selected by the response's `Vary` header
+    pub vary: Vec<(String, Option<String>)>,
+}
+
+impl CachedResponse {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub(crate) fn header_map(&self) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in &self.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                map.append(name, value);
+            }
+        }
+        map
+    }
+
+    pub(crate) fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK)
+    }
+
+    fn cache_control(&self) -> CacheControl {
+        CacheControl::parse(self.headers_named("cache-control"))
+    }
+
+    fn headers_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.headers
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// How long the response is fresh for after it was generated (RFC 9111 section 4.2.1)
+    pub(crate) fn freshness_lifetime(&self) -> Duration {
+        let cache_control = self.cache_control();
+        if cache_control.no_cache {
+            return Duration::ZERO;
+        }
+        if let Some(max_age) = cache_control.max_age {
+            return max_age;
+        }
+
+        let date = self
+            .header("date")
+            .and_then(|date| httpdate::parse_http_date(date).ok())
+            .unwrap_or(self.stored_at);
+        if let Some(expires) = self.header("expires") {
+            return httpdate::parse_http_date(expires)
+                .ok()
+                .and_then(|expires| expires.duration_since(date).ok())
+                .unwrap_or_default();
+        }
+
+        // Heuristic freshness: 10% of the time since the resource was last modified
+        self.header("last-modified")
+            .and_then(|modified| httpdate::parse_http_date(modified).ok())
+            .and_then(|modified| date.duration_since(modified).ok())
+            .map(|age| age / 10)
+            .unwrap_or_default()
+    }
+
+    /// The age of the response, including the `Age` reported by upstream caches
+    pub(crate) fn age(&self, now: SystemTime) -> Duration {
+        let upstream_age = self
+            .header("age")
+            .and_then(|age| age.trim().parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or_default();
+        upstream_age + now.duration_since(self.stored_at).unwrap_or_default()
+    }
+
+    pub(crate) fn is_fresh(&self, now: SystemTime) -> bool {
+        self.age(now) < self.freshness_lifetime()
+    }
+
+    /// How long ago the response stopped being fresh
+    fn staleness(&self, now: SystemTime) -> Duration {
+        self.age(now).saturating_sub(self.freshness_lifetime())
+    }
+
+    /// True if the response may be served while it is refreshed in the background (RFC 5861)
+    pub(crate) fn may_serve_while_revalidating(&self, now: SystemTime) -> bool {
+        self.cache_control()
+            .stale_while_revalidate
+            .is_some_and(|window| self.staleness(now) <= window)
+    }
+
+    /// True if the response may be served when the server fails or cannot be reached (RFC 5861)
+    pub(crate) fn may_serve_on_error(&self, now: SystemTime) -> bool {
+        self.cache_control()
+            .stale_if_error
+            .is_some_and(|window| self.staleness(now) <= window)
+    }
+
+    /// Merges the headers of a `304 Not Modified` into this response and restarts its age
+    pub(crate) fn revalidated(&mut self, headers: &HeaderMap, now: SystemTime) {
+        for name in headers.keys() {
+            let name = name.as_str();
+            if name == "content-length" {
+                continue;
+            }
+            self.headers.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+            for value in headers.get_all(name) {
+                if let Ok(value) = value.to_str() {
+                    self.headers.push((name.to_string(), value.to_string()));
+                }
+            }
+        }
+        self.stored_at = now;
+    }
+
+    /// Returns true if `request` selects this response according to its `Vary` header
+    fn matches_vary(&self, request: &Request) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| request_header(request, name).as_deref() == value.as_deref())
+    }
+}
+
+fn request_header(request: &Request, name: &str) -> Option<String> {
+    request
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// The `Cache-Control` directives the cache acts on
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct CacheControl {
+    pub(crate) no_store: bool,
+    pub(crate) no_cache: bool,
+    pub(crate) max_age: Option<Duration>,
+    pub(crate) stale_while_revalidate: Option<Duration>,
+    pub(crate) stale_if_error: Option<Duration>,
+}
+
+impl CacheControl {
+    pub(crate) fn parse<'a>(values: impl Iterator<Item = &'a str>) -> Self {
+        let mut cache_control = Self::default();
+        for directive in values.flat_map(|value| value.split(',')) {
+            let (name, argument) = match directive.split_once('=') {
+                Some((name, argument)) => (name.trim(), Some(argument.trim().trim_matches('"'))),
+                None => (directive.trim(), None),
+            };
+            let seconds = || {
+                argument
+                    .and_then(|secs| secs.parse().ok())
+                    .map(Duration::from_secs)
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "no-store" => cache_control.no_store = true,
+                "no-cache" => cache_control.no_cache = true,
+                "max-age" => cache_control.max_age = seconds(),
+                "stale-while-revalidate" => cache_control.stale_while_revalidate = seconds(),
+                "stale-if-error" => cache_control.stale_if_error = seconds(),
+                _ => {}
+            }
+        }
+        cache_control
+    }
+
+    fn from_headers(headers: &HeaderMap) -> Self {
+        Self::parse(
+            headers
+                .get_all(reqwest::header::CACHE_CONTROL)
+                .iter()
+                .filter_map(|value| value.to_str().ok()),
+        )
+    }
+}
+
+/// Storage backend for the HTTP cache.
+///
+/// Keys are derived from the request method and URL; selecting between `Vary` variants is done
+/// by the cache itself.
+pub trait CacheStore: Debug + Send + Sync {
+    fn get(&self, key: &str) -> Option<CachedResponse>;
+    fn put(&self, key: &str, response: CachedResponse);
+    fn remove(&self, key: &str);
+
+    /// True if the store does blocking I/O, e.g. on disk. `HttpCache` then calls it on tokio's
+    /// blocking thread pool, so the runtime's worker threads never wait for it
+    fn is_blocking(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    entries: HashMap<String, (CachedResponse, u64)>,
+    /// Maps the last access tick to the key used at that tick
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl LruState {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some((_, last_used)) = self.entries.get_mut(key) {
+            self.recency.remove(last_used);
+            *last_used = tick;
+            self.recency.insert(tick, key.to_string());
+        }
+    }
+}
+
+/// An in-memory `CacheStore` that evicts the least recently used entry once `capacity` is reached
+#[derive(Debug)]
+pub struct MemoryCacheStore {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+impl MemoryCacheStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Default::default(),
+        }
+    }
+}
+
+impl CacheStore for MemoryCacheStore {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut state = self.state.lock().unwrap();
+        state.touch(key);
+        state.entries.get(key).map(|(response, _)| response.clone())
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if let Some((cached, _)) = state.entries.get_mut(key) {
+            // Keeps the current tick, so `touch` can remove it from `recency`
+            *cached = response;
+        } else {
+            if state.entries.len() >= self.capacity {
+                if let Some((_, oldest)) = state.recency.pop_first() {
+                    state.entries.remove(&oldest);
+                }
+            }
+            state.entries.insert(key.to_string(), (response, 0));
+        }
+        state.touch(key);
+    }
+
+    fn remove(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some((_, last_used)) = state.entries.remove(key) {
+            state.recency.remove(&last_used);
+        }
+    }
+}
+
+/// A private HTTP cache (RFC 9111) for `GET` requests.
+///
+/// Fresh responses are served without contacting the server. Stale responses that carry an
+/// `ETag` or `Last-Modified` are revalidated with `If-None-Match` / `If-Modified-Since`.
+/// Successful unsafe requests (`POST`, `PUT`, `PATCH`, `DELETE`) invalidate the cached `GET` of
+/// the same URL.
+///
+/// The `stale-while-revalidate` and `stale-if-error` extensions (RFC 5861) are honored: within
+/// those windows a stale response is returned immediately while it is refreshed in the
+/// background, or returned in place of a network failure or a `500`, `502`, `503` or `504`.
+///
+/// # Example
+/// ```rust
+/// use rust_fetch::{Fetch, FetchConfig, HttpCache};
+///
+/// let client = Fetch::new(
+///     "https://reference-data.local",
+///     Some(FetchConfig {
+///         cache: Some(HttpCache::memory(1000)),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    pub(crate) store: Arc<dyn CacheStore>,
+    pub(crate) offline_fallback: bool,
+    /// Keys with a background refresh in flight
+    refreshing: Arc<Mutex<HashSet<String>>>,
+}
+
+impl HttpCache {
+    pub fn new(store: Arc<dyn CacheStore>) -> Self {
+        Self {
+            store,
+            offline_fallback: false,
+            refreshing: Default::default(),
+        }
+    }
+
+    /// When enabled, a request that cannot reach the server is answered with the last stored
+    /// response instead of failing with `FetchError::UnableToSendRequest`. Such responses have a
+    /// `CacheStatus::Stale` cache status.
+    pub fn with_offline_fallback(mut self, offline_fallback: bool) -> Self {
+        self.offline_fallback = offline_fallback;
+        self
+    }
+
+    /// Creates a cache backed by a `MemoryCacheStore` holding up to `capacity` responses
+    pub fn memory(capacity: usize) -> Self {
+        Self::new(Arc::new(MemoryCacheStore::new(capacity)))
+    }
+
+    pub(crate) fn key(method: &Method, url: &reqwest::Url) -> String {
+        format!("{method} {url}")
+    }
+
+    /// Returns true if the cache may be used for `request` at all
+    pub(crate) fn handles(request: &Request) -> bool {
+        request.method() == Method::GET
+            && !CacheControl::from_headers(request.headers()).no_store
+    }
+
+    /// Runs `op` against the store, on the blocking thread pool if the store blocks
+    async fn with_store<R>(&self, op: impl FnOnce(&dyn CacheStore) -> R + Send + 'static) -> R
+    where
+        R: Send + 'static,
+    {
+        if !self.store.is_blocking() {
+            return op(self.store.as_ref());
+        }
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || op(store.as_ref()))
+            .await
+            .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+    }
+
+    /// Looks up the response stored under `key` and selected by `request`
+    pub(crate) async fn lookup(&self, key: &str, request: &Request) -> Option<CachedResponse> {
+        let key = key.to_string();
+        self.with_store(move |store| store.get(&key))
+            .await
+            .filter(|cached| cached.matches_vary(request))
+    }
+
+    /// Stores `cached` under `key` as is, e.g. after it was revalidated
+    pub(crate) async fn put(&self, key: &str, cached: CachedResponse) {
+        let key = key.to_string();
+        self.with_store(move |store| store.put(&key, cached)).await
+    }
+
+    /// True if the request asks to bypass fresh cached responses (`Cache-Control: no-cache`)
+    pub(crate) fn requires_revalidation(request: &Request) -> bool {
+        CacheControl::from_headers(request.headers()).no_cache
+    }
+
+    /// Stores the response under `key` if it is cacheable. Returns the stored entry
+    pub(crate) async fn store(
+        &self,
+        key: &str,
+        request: &Request,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Option<CachedResponse> {
+        if !is_cacheable(status, headers) {
+            return None;
+        }
+
+        let mut vary = Vec::new();
+        for value in headers.get_all(reqwest::header::VARY) {
+            for name in value.to_str().ok()?.split(',') {
+                let name = name.trim().to_ascii_lowercase();
+                if name == "*" {
+                    return None;
+                }
+                let value = request_header(request, &name);
+                vary.push((name, value));
+            }
+        }
+
+        let cached = CachedResponse {
+            status: status.as_u16(),
+            headers: headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            body: body.to_vec(),
+            stored_at: SystemTime::now(),
+            vary,
+        };
+        self.put(key, cached.clone()).await;
+        Some(cached)
+    }
+
+    /// Adds `If-None-Match` / `If-Modified-Since` validators from `cached` to `request`
+    pub(crate) fn add_validators(request: &mut Request, cached: &CachedResponse) {
+        let headers = request.headers_mut();
+        if let Some(etag) = cached.header("etag").and_then(|v| HeaderValue::from_str(v).ok()) {
+            headers.insert(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(modified) = cached
+            .header("last-modified")
+            .and_then(|v| HeaderValue::from_str(v).ok())
+        {
+            headers.insert(reqwest::header::IF_MODIFIED_SINCE, modified);
+        }
+    }
+
+    /// Marks `key` as being refreshed. Returns false if a refresh is already in flight
+    pub(crate) fn begin_refresh(&self, key: &str) -> bool {
+        self.refreshing.lock().unwrap().insert(key.to_string())
+    }
+
+    pub(crate) fn end_refresh(&self, key: &str) {
+        self.refreshing.lock().unwrap().remove(key);
+    }
+
+    /// Removes the cached `GET` response stored under `key` after a successful unsafe request
+    pub(crate) async fn invalidate(&self, key: &str) {
+        let key = key.to_string();
+        self.with_store(move |store| store.remove(&key)).await
+    }
+}
+
+/// Only final responses with a status that is cacheable by default and either explicit freshness
+/// information or a validator are stored
+fn is_cacheable(status: StatusCode, headers: &HeaderMap) -> bool {
+    let cacheable_status = matches!(
+        status.as_u16(),
+        200 | 203 | 204 | 300 | 301 | 308 | 404 | 405 | 410 | 414 | 501
+    );
+    let cache_control = CacheControl::from_headers(headers);
+    let has_freshness_or_validator = cache_control.max_age.is_some()
+        || cache_control.no_cache
+        || headers.contains_key(reqwest::header::EXPIRES)
+        || headers.contains_key(reqwest::header::ETAG)
+        || headers.contains_key(reqwest::header::LAST_MODIFIED);
+
+    cacheable_status && !cache_control.no_store && has_freshness_or_validator
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{CacheControl, CacheStore, CachedResponse, MemoryCacheStore};
+
+    fn cached(headers: &[(&str, &str)], stored_at: SystemTime) -> CachedResponse {
+        CachedResponse {
+            status: 200,
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body: Vec::new(),
+            stored_at,
+            vary: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_cache_control() {
+        let cache_control = CacheControl::parse(["public, max-age=\"3600\"", "no-cache"].into_iter());
+        assert_eq!(Some(Duration::from_secs(3600)), cache_control.max_age);
+        assert!(cache_control.no_cache);
+        assert!(!cache_control.no_store);
+    }
+
+    #[test]
+    fn test_freshness_from_max_age_and_age() {
+        let now = SystemTime::now();
+        let response = cached(&[("cache-control", "max-age=60"), ("age", "50")], now);
+        assert!(response.is_fresh(now + Duration::from_secs(5)));
+        assert!(!response.is_fresh(now + Duration::from_secs(15)));
+    }
+
+    #[test]
+    fn test_stale_windows() {
+        let now = SystemTime::now();
+        let response = cached(
+            &[(
+                "cache-control",
+                "max-age=60, stale-while-revalidate=30, stale-if-error=600",
+            )],
+            now,
+        );
+        let later = |secs| now + Duration::from_secs(secs);
+
+        assert!(response.may_serve_while_revalidating(later(80)));
+        assert!(!response.may_serve_while_revalidating(later(100)));
+        assert!(response.may_serve_on_error(later(600)));
+        assert!(!response.may_serve_on_error(later(700)));
+        assert!(!cached(&[("cache-control", "max-age=60")], now).may_serve_on_error(later(61)));
+    }
+
+    #[test]
+    fn test_heuristic_freshness_from_last_modified() {
+        let response = cached(
+            &[
+                ("date", "Sun, 11 Oct 2026 00:00:00 GMT"),
+                ("last-modified", "Thu, 01 Oct 2026 00:00:00 GMT"),
+            ],
+            SystemTime::now(),
+        );
+        assert_eq!(Duration::from_secs(86_400), response.freshness_lifetime());
+    }
+
+    #[test]
+    fn test_memory_store_evicts_least_recently_used() {
+        let store = MemoryCacheStore::new(2);
+        let now = SystemTime::now();
+        store.put("a", cached(&[], now));
+        store.put("b", cached(&[], now));
+        store.get("a");
+        store.put("c", cached(&[], now));
+
+        assert!(store.get("a").is_some());
+        assert!(store.get("b").is_none());
+        assert!(store.get("c").is_some());
+    }
+
+    #[test]
+    fn test_memory_store_overwrite_keeps_eviction_order() {
+        let store = MemoryCacheStore::new(2);
+        let now = SystemTime::now();
+        store.put("a", cached(&[], now));
+        store.put("b", cached(&[], now));
+        store.put("a", cached(&[], now));
+        store.put("c", cached(&[], now));
+
+        assert!(store.get("a").is_some());
+        assert!(store.get("b").is_none());
+        assert!(store.get("c").is_some());
+        let state = store.state.lock().unwrap();
+        assert!(state.entries.len() <= 2);
+        assert_eq!(state.entries.len(), state.recency.len());
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::Url;
+use tokio::time::Instant;
+
+use crate::error::{FetchError, FetchResult};
+
+/// The state of the circuit of a host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests flow normally while their outcomes are recorded
+    Closed,
+    /// Requests fail fast with `FetchError::CircuitOpen` until the cool-down has passed
+    Open,
+    /// A limited number of probe requests decide whether the circuit closes or opens again
+    HalfOpen,
+}
+
+type StateChangeCallback = dyn Fn(&str, CircuitState, CircuitState) + Send + Sync;
+
+#[derive(Debug)]
+struct HostCircuit {
+    state: CircuitState,
+    /// Outcomes of the most recent requests while closed, `true` for failures
+    outcomes: VecDeque<bool>,
+    /// When the circuit last opened or became half-open
+    changed_at: Instant,
+    probes_in_flight: u32,
+    probe_successes: u32,
+}
+
+impl HostCircuit {
+    fn new(now: Instant) -> Self {
+        Self {
+            state: CircuitState::Closed,
+            outcomes: VecDeque::new(),
+            changed_at: now,
+            probes_in_flight: 0,
+            probe_successes: 0,
+        }
+    }
+}
+
+/// Stops sending requests to a host that keeps failing, so callers fail fast instead of waiting
+/// for timeouts.
+///
+/// Connection errors and `5xx` responses count as failures. Once at least `minimum_requests` of
+/// the last `window` requests to a host were sent and `failure_rate` of them failed, the circuit
+/// opens. After `cool_down`, `half_open_requests` probes are let through: if they all succeed the
+/// circuit closes, otherwise it opens again.
+///
+/// Clones share their circuits.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rust_fetch::{CircuitBreaker, Fetch, FetchConfig};
+///
+/// let breaker = CircuitBreaker::new()
+///     .with_failure_rate(0.5)
+///     .with_cool_down(Duration::from_secs(10))
+///     .on_state_change(|host, from, to| eprintln!("circuit for {host}: {from:?} -> {to:?}"));
+/// let client = Fetch::new(
+///     "https://inventory.local",
+///     Some(FetchConfig {
+///         circuit_breaker: Some(breaker),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// ```
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    failure_rate: f64,
+    minimum_requests: u32,
+    window: u32,
+    cool_down: Duration,
+    half_open_requests: u32,
+    on_state_change: Option<Arc<StateChangeCallback>>,
+    hosts: Arc<Mutex<HashMap<String, HostCircuit>>>,
+}
+
+impl Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("failure_rate", &self.failure_rate)
+            .field("minimum_requests", &self.minimum_requests)
+            .field("window", &self.window)
+            .field("cool_down", &self.cool_down)
+            .field("half_open_requests", &self.half_open_requests)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CircuitBreaker {
+    /// Opens when half of at least 10 of the last 20 requests failed, and probes again after 30
+    /// seconds with a single request
+    pub fn new() -> Self {
+        Self {
+            failure_rate: 0.5,
+            minimum_requests: 10,
+            window: 20,
+            cool_down: Duration::from_secs(30),
+            half_open_requests: 1,
+            on_state_change: None,
+            hosts: Default::default(),
+        }
+    }
+
+    /// The fraction of failed requests (between 0 and 1) that opens the circuit
+    pub fn with_failure_rate(mut self, failure_rate: f64) -> Self {
+        self.failure_rate = failure_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// How many requests must have been recorded before the failure rate is evaluated
+    pub fn with_minimum_requests(mut self, minimum_requests: u32) -> Self {
+        self.minimum_requests = minimum_requests.max(1);
+        self.window = self.window.max(self.minimum_requests);
+        self
+    }
+
+    /// How many of the most recent requests the failure rate is computed over
+    pub fn with_window(mut self, window: u32) -> Self {
+        self.window = window.max(self.minimum_requests);
+        self
+    }
+
+    /// How long the circuit stays open before probing the host again
+    pub fn with_cool_down(mut self, cool_down: Duration) -> Self {
+        self.cool_down = cool_down;
+        self
+    }
+
+    /// How many probes are let through, and must succeed, while half-open
+    pub fn with_half_open_requests(mut self, half_open_requests: u32) -> Self {
+        self.half_open_requests = half_open_requests.max(1);
+        self
+    }
+
+    /// Calls `callback` with the host, the previous and the new state whenever a circuit changes
+    /// state
+    pub fn on_state_change<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str, CircuitState, CircuitState) + Send + Sync + 'static,
+    {
+        self.on_state_change = Some(Arc::new(callback));
+        self
+    }
+
+    /// The current state of the circuit of `host`
+    pub fn state(&self, host: &str) -> CircuitState {
+        self.hosts
+            .lock()
+            .unwrap()
+            .get(host)
+            .map(|circuit| circuit.state)
+            .unwrap_or(CircuitState::Closed)
+    }
+
+    fn transition(circuit: &mut HostCircuit, to: CircuitState, now: Instant) -> CircuitState {
+        let from = circuit.state;
+        circuit.state = to;
+        circuit.outcomes.clear();
+        circuit.probes_in_flight = 0;
+        circuit.probe_successes = 0;
+        circuit.changed_at = now;
+        from
+    }
+
+    fn notify(&self, host: &str, change: Option<(CircuitState, CircuitState)>) {
+        if let (Some(callback), Some((from, to))) = (&self.on_state_change, change) {
+            callback(host, from, to);
+        }
+    }
+
+    fn try_acquire(&self, host: &str, now: Instant) -> FetchResult<()> {
+        let mut change = None;
+        let result = {
+            let mut hosts = self.hosts.lock().unwrap();
+            let circuit = hosts
+                .entry(host.to_string())
+                .or_insert_with(|| HostCircuit::new(now));
+
+            let elapsed = now.saturating_duration_since(circuit.changed_at);
+            if circuit.state == CircuitState::Open && elapsed >= self.cool_down {
+                let from = Self::transition(circuit, CircuitState::HalfOpen, now);
+                change = Some((from, CircuitState::HalfOpen));
+            }
+
+            let elapsed = now.saturating_duration_since(circuit.changed_at);
+            match circuit.state {
+                CircuitState::Closed => Ok(()),
+                CircuitState::HalfOpen if circuit.probes_in_flight < self.half_open_requests => {
+                    circuit.probes_in_flight += 1;
+                    Ok(())
+                }
+                // Probes that never reported back (e.g. cancelled calls) are given up on after
+                // another cool-down
+                CircuitState::HalfOpen if elapsed >= self.cool_down => {
+                    circuit.changed_at = now;
+                    Ok(())
+                }
+                _ => Err(FetchError::CircuitOpen {
+                    host: host.to_string(),
+                    retry_after: self.cool_down.saturating_sub(elapsed),
+                }),
+            }
+        };
+        self.notify(host, change);
+        result
+    }
+
+    fn record(&self, host: &str, failed: bool, now: Instant) {
+        let mut change = None;
+        {
+            let mut hosts = self.hosts.lock().unwrap();
+            let Some(circuit) = hosts.get_mut(host) else {
+                return;
+            };
+
+            match circuit.state {
+                CircuitState::Closed => {
+                    circuit.outcomes.push_back(failed);
+                    if circuit.outcomes.len() > self.window as usize {
+                        circuit.outcomes.pop_front();
+                    }
+                    let recorded = circuit.outcomes.len();
+                    let failures = circuit.outcomes.iter().filter(|failed| **failed).count();
+                    if recorded >= self.minimum_requests as usize
+                        && failures as f64 >= recorded as f64 * self.failure_rate
+                        && failures > 0
+                    {
+                        let from = Self::transition(circuit, CircuitState::Open, now);
+                        change = Some((from, CircuitState::Open));
+                    }
+                }
+                CircuitState::HalfOpen if failed => {
+                    let from = Self::transition(circuit, CircuitState::Open, now);
+                    change = Some((from, CircuitState::Open));
+                }
+                CircuitState::HalfOpen => {
+                    circuit.probe_successes += 1;
+                    if circuit.probe_successes >= self.half_open_requests {
+                        let from = Self::transition(circuit, CircuitState::Closed, now);
+                        change = Some((from, CircuitState::Closed));
+                    }
+                }
+                // Requests that were already in flight when the circuit opened
+                CircuitState::Open => {}
+            }
+        }
+        self.notify(host, change);
+    }
+
+    pub(crate) fn acquire(&self, url: &Url) -> FetchResult<()> {
+        self.try_acquire(url.host_str().unwrap_or_default(), Instant::now())
+    }
+
+    pub(crate) fn record_outcome(&self, url: &Url, failed: bool) {
+        self.record(url.host_str().unwrap_or_default(), failed, Instant::now());
+    }
+
+    /// Gives back the probe slot of a request that was acquired but never sent
+    pub(crate) fn release(&self, url: &Url) {
+        let mut hosts = self.hosts.lock().unwrap();
+        if let Some(circuit) = hosts.get_mut(url.host_str().unwrap_or_default()) {
+            if circuit.state == CircuitState::HalfOpen {
+                circuit.probes_in_flight = circuit.probes_in_flight.saturating_sub(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio::time::Instant;
+
+    use super::{CircuitBreaker, CircuitState};
+    use crate::FetchError;
+
+    #[test]
+    fn test_circuit_opens_probes_and_closes() {
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = changes.clone();
+        let breaker = CircuitBreaker::new()
+            .with_minimum_requests(4)
+            .with_failure_rate(0.5)
+            .with_cool_down(Duration::from_secs(10))
+            .on_state_change(move |host, from, to| {
+                recorded.lock().unwrap().push((host.to_string(), from, to))
+            });
+        let now = Instant::now();
+
+        for failed in [false, true, false, true] {
+            breaker.try_acquire("db.local", now).unwrap();
+            breaker.record("db.local", failed, now);
+        }
+        assert_eq!(CircuitState::Open, breaker.state("db.local"));
+        match breaker.try_acquire("db.local", now + Duration::from_secs(4)) {
+            Err(FetchError::CircuitOpen { retry_after, .. }) => {
+                assert_eq!(Duration::from_secs(6), retry_after)
+            }
+            other => panic!("expected an open circuit, got {other:?}"),
+        }
+
+        let later = now + Duration::from_secs(10);
+        assert!(breaker.try_acquire("db.local", later).is_ok());
+        assert!(breaker.try_acquire("db.local", later).is_err());
+        breaker.record("db.local", false, later);
+        assert_eq!(CircuitState::Closed, breaker.state("db.local"));
+
+        assert_eq!(
+            vec![
+                (
+                    "db.local".to_string(),
+                    CircuitState::Closed,
+                    CircuitState::Open
+                ),
+                (
+                    "db.local".to_string(),
+                    CircuitState::Open,
+                    CircuitState::HalfOpen
+                ),
+                (
+                    "db.local".to_string(),
+                    CircuitState::HalfOpen,
+                    CircuitState::Closed
+                ),
+            ],
+            *changes.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_failed_probe_reopens_circuit() {
+        let breaker = CircuitBreaker::new()
+            .with_minimum_requests(1)
+            .with_cool_down(Duration::from_secs(1));
+        let now = Instant::now();
+
+        breaker.try_acquire("a", now).unwrap();
+        breaker.record("a", true, now);
+        assert!(breaker.try_acquire("b", now).is_ok());
+
+        let later = now + Duration::from_secs(1);
+        breaker.try_acquire("a", later).unwrap();
+        breaker.record("a", true, later);
+        assert_eq!(CircuitState::Open, breaker.state("a"));
+        assert!(breaker.try_acquire("a", later).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{header::HeaderMap, StatusCode};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::error::{FetchError, FetchResult};
+
+/// Hash algorithms for `Content-Digest` and `Repr-Digest` fields (RFC 9530)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentDigestAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl ContentDigestAlgorithm {
+    fn key(&self) -> &'static str {
+        match self {
+            ContentDigestAlgorithm::Sha256 => "sha-256",
+            ContentDigestAlgorithm::Sha512 => "sha-512",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "sha-256" => Some(ContentDigestAlgorithm::Sha256),
+            "sha-512" => Some(ContentDigestAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    fn digest(&self, body: &[u8]) -> Vec<u8> {
+        match self {
+            ContentDigestAlgorithm::Sha256 => Sha256::digest(body).to_vec(),
+            ContentDigestAlgorithm::Sha512 => Sha512::digest(body).to_vec(),
+        }
+    }
+
+    /// Formats the digest of `body` as a `Content-Digest` field value, e.g. `sha-256=:...:`
+    pub fn header_value(&self, body: &[u8]) -> String {
+        format!("{}=:{}:", self.key(), STANDARD.encode(self.digest(body)))
+    }
+}
+
+/// Checks `body` against the `Content-Digest` header, and against `Repr-Digest` unless `body` is
+/// only part of the representation (a `206` response). Both digests cover the content-coded bytes,
+/// so `body` must not have been decoded.
+///
+/// Digests using algorithms other than sha-256 and sha-512 are ignored.
+pub(crate) fn verify(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> FetchResult<()> {
+    let mut fields = vec!["content-digest"];
+    if status != StatusCode::PARTIAL_CONTENT {
+        fields.push("repr-digest");
+    }
+
+    for field in fields {
+        for value in headers.get_all(field) {
+            let Ok(value) = value.to_str() else {
+                return Err(mismatch(field, "unparseable"));
+            };
+            for member in value.split(',') {
+                let Some((key, encoded)) = member.trim().split_once('=') else {
+                    continue;
+                };
+                let Some(algorithm) = ContentDigestAlgorithm::from_key(key.trim()) else {
+                    continue;
+                };
+                let expected = encoded
+                    .trim()
+                    .strip_prefix(':')
+                    .and_then(|encoded| encoded.strip_suffix(':'))
+                    .and_then(|encoded| STANDARD.decode(encoded).ok());
+                if expected.as_deref() != Some(algorithm.digest(body).as_slice()) {
+                    return Err(mismatch(field, algorithm.key()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn mismatch(header: &str, algorithm: &str) -> FetchError {
+    FetchError::DigestMismatch {
+        header: header.to_string(),
+        algorithm: algorithm.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{
+        header::{HeaderMap, HeaderValue},
+        StatusCode,
+    };
+
+    use super::{verify, ContentDigestAlgorithm};
+
+    // Examples from RFC 9530 section 2
+    const BODY: &[u8] = b"{\"hello\": \"world\"}";
+    const SHA_256: &str = "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:";
+    const SHA_512: &str = "sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:";
+
+    #[test]
+    fn test_header_value() {
+        assert_eq!(SHA_256, ContentDigestAlgorithm::Sha256.header_value(BODY));
+        assert_eq!(SHA_512, ContentDigestAlgorithm::Sha512.header_value(BODY));
+    }
+
+    #[test]
+    fn test_verify_matching_and_mismatching_digest() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "content-digest",
+            HeaderValue::from_str(&format!("{SHA_256}, {SHA_512}")).unwrap(),
+        );
+        assert!(verify(StatusCode::OK, &headers, BODY).is_ok());
+        assert!(verify(StatusCode::OK, &headers, b"{\"hello\": \"tampered\"}").is_err());
+    }
+
+    #[test]
+    fn test_verify_repr_digest_of_encoded_and_partial_content() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-encoding", HeaderValue::from_static("gzip"));
+        headers.insert("repr-digest", HeaderValue::from_static(SHA_256));
+        assert!(verify(StatusCode::OK, &headers, BODY).is_ok());
+        assert!(verify(StatusCode::OK, &headers, &BODY[..4]).is_err());
+        assert!(verify(StatusCode::PARTIAL_CONTENT, &headers, &BODY[..4]).is_ok());
+    }
+
+    #[test]
+    fn test_verify_ignores_unknown_algorithms() {
+        let mut headers = HeaderMap::new();
+        headers.insert("repr-digest", HeaderValue::from_static("md5=:AAAA:"));
+        assert!(verify(StatusCode::OK, &headers, BODY).is_ok());
+    }
+}
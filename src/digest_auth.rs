@@ -0,0 +1,373 @@
+use std::{
+    fmt::Debug,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use md5::Md5;
+use rand::RngCore;
+use reqwest::{header::HeaderValue, Request, Response};
+use sha2::{Digest, Sha256, Sha512_256};
+
+use crate::{
+    error::{FetchError, FetchResult},
+    utils::parse_params,
+};
+
+/// The hash algorithms defined for Digest auth by RFC 7616
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+    Sha512_256,
+    Sha512_256Sess,
+}
+
+impl DigestAlgorithm {
+    fn is_session(&self) -> bool {
+        matches!(
+            self,
+            Self::Md5Sess | Self::Sha256Sess | Self::Sha512_256Sess
+        )
+    }
+
+    /// Higher is preferred when a server offers several challenges
+    fn strength(&self) -> u8 {
+        match self {
+            Self::Md5 | Self::Md5Sess => 0,
+            Self::Sha256 | Self::Sha256Sess => 1,
+            Self::Sha512_256 | Self::Sha512_256Sess => 2,
+        }
+    }
+
+    fn hash(&self, data: &str) -> String {
+        match self {
+            Self::Md5 | Self::Md5Sess => hex::encode(Md5::digest(data.as_bytes())),
+            Self::Sha256 | Self::Sha256Sess => hex::encode(Sha256::digest(data.as_bytes())),
+            Self::Sha512_256 | Self::Sha512_256Sess => {
+                hex::encode(Sha512_256::digest(data.as_bytes()))
+            }
+        }
+    }
+
+    fn hash_bytes(&self, data: &[u8]) -> String {
+        match self {
+            Self::Md5 | Self::Md5Sess => hex::encode(Md5::digest(data)),
+            Self::Sha256 | Self::Sha256Sess => hex::encode(Sha256::digest(data)),
+            Self::Sha512_256 | Self::Sha512_256Sess => hex::encode(Sha512_256::digest(data)),
+        }
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "MD5" => Ok(Self::Md5),
+            "MD5-SESS" => Ok(Self::Md5Sess),
+            "SHA-256" => Ok(Self::Sha256),
+            "SHA-256-SESS" => Ok(Self::Sha256Sess),
+            "SHA-512-256" => Ok(Self::Sha512_256),
+            "SHA-512-256-SESS" => Ok(Self::Sha512_256Sess),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Md5 => write!(f, "MD5"),
+            Self::Md5Sess => write!(f, "MD5-sess"),
+            Self::Sha256 => write!(f, "SHA-256"),
+            Self::Sha256Sess => write!(f, "SHA-256-sess"),
+            Self::Sha512_256 => write!(f, "SHA-512-256"),
+            Self::Sha512_256Sess => write!(f, "SHA-512-256-sess"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Qop {
+    Auth,
+    AuthInt,
+}
+
+impl Qop {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Qop::Auth => "auth",
+            Qop::AuthInt => "auth-int",
+        }
+    }
+}
+
+/// A parsed `WWW-Authenticate: Digest` challenge
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DigestChallenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: DigestAlgorithm,
+    qop: Option<Qop>,
+}
+
+impl DigestChallenge {
+    /// Parses the parameters of a single challenge, without the leading `Digest` scheme
+    fn parse(params: &str) -> Option<Self> {
+        let mut realm = None;
+        let mut nonce = None;
+        let mut opaque = None;
+        let mut algorithm = DigestAlgorithm::Md5;
+        let mut qop_options: Option<String> = None;
+
+        for (key, value) in parse_params(params) {
+            match key.to_ascii_lowercase().as_str() {
+                "realm" => realm = Some(value),
+                "nonce" => nonce = Some(value),
+                "opaque" => opaque = Some(value),
+                "algorithm" => algorithm = value.parse().ok()?,
+                "qop" => qop_options = Some(value),
+                _ => {}
+            }
+        }
+
+        // Prefer auth over auth-int, since auth-int requires hashing the whole body
+        let qop = qop_options.map(|options| {
+            let options: Vec<&str> = options.split(',').map(str::trim).collect();
+            if options.contains(&"auth") || !options.contains(&"auth-int") {
+                Qop::Auth
+            } else {
+                Qop::AuthInt
+            }
+        });
+
+        Some(Self {
+            realm: realm?,
+            nonce: nonce?,
+            opaque,
+            algorithm,
+            qop,
+        })
+    }
+
+    /// Picks the strongest Digest challenge out of all `WWW-Authenticate` headers of `response`
+    fn from_response(response: &Response) -> Option<Self> {
+        response
+            .headers()
+            .get_all(reqwest::header::WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| {
+                let (scheme, params) = value.trim().split_once(' ')?;
+                scheme
+                    .eq_ignore_ascii_case("digest")
+                    .then(|| Self::parse(params))
+                    .flatten()
+            })
+            .max_by_key(|challenge| challenge.algorithm.strength())
+    }
+}
+
+#[derive(Debug)]
+struct DigestState {
+    challenge: DigestChallenge,
+    nonce_count: u32,
+}
+
+/// HTTP Digest authentication (RFC 7616).
+///
+/// The first request is sent without credentials. When the server answers
+/// `401 WWW-Authenticate: Digest`, the challenge is stored and the request is re-sent with an
+/// `Authorization` header. Later requests reuse the nonce with an increasing nonce count, until
+/// the server issues a new challenge.
+///
+/// # Example
+/// ```rust
+/// use rust_fetch::{DigestAuth, Fetch, FetchAuth, FetchConfig};
+///
+/// let client = Fetch::new(
+///     "http://192.168.1.20",
+///     Some(FetchConfig {
+///         auth: Some(FetchAuth::Digest(DigestAuth::new("admin", "password"))),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// ```
+#[derive(Clone)]
+pub struct DigestAuth {
+    username: String,
+    password: String,
+    state: Arc<Mutex<Option<DigestState>>>,
+}
+
+impl Debug for DigestAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DigestAuth")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DigestAuth {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_string(),
+            password: password.to_string(),
+            state: Default::default(),
+        }
+    }
+
+    pub(crate) fn identity(&self) -> String {
+        format!("digest {}", self.username)
+    }
+
+    /// Adds an `Authorization` header if a challenge has been received. Returns false otherwise
+    pub(crate) fn authorize(&self, request: &mut Request) -> FetchResult<bool> {
+        let (challenge, nonce_count) = {
+            let mut state = self.state.lock().unwrap();
+            let Some(state) = state.as_mut() else {
+                return Ok(false);
+            };
+            state.nonce_count += 1;
+            (state.challenge.clone(), state.nonce_count)
+        };
+
+        let mut cnonce = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut cnonce);
+        let header = self.authorization(&challenge, request, nonce_count, &hex::encode(cnonce));
+        let value = HeaderValue::from_str(&header).map_err(|_| {
+            FetchError::HeaderParseError(
+                reqwest::header::AUTHORIZATION.to_string(),
+                "Digest <redacted>".to_string(),
+            )
+        })?;
+        request
+            .headers_mut()
+            .insert(reqwest::header::AUTHORIZATION, value);
+        Ok(true)
+    }
+
+    /// Stores the challenge of a `401` response. Returns true if the request should be re-sent
+    pub(crate) fn challenge(&self, response: &Response) -> bool {
+        let Some(challenge) = DigestChallenge::from_response(response) else {
+            return false;
+        };
+        *self.state.lock().unwrap() = Some(DigestState {
+            challenge,
+            nonce_count: 0,
+        });
+        true
+    }
+
+    fn authorization(
+        &self,
+        challenge: &DigestChallenge,
+        request: &Request,
+        nonce_count: u32,
+        cnonce: &str,
+    ) -> String {
+        let url = request.url();
+        let uri = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .unwrap_or_default();
+
+        let nc = format!("{nonce_count:08x}");
+        let response = self.response(challenge, request.method().as_str(), &uri, body, &nc, cnonce);
+
+        let mut header = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+            self.username, challenge.realm, challenge.nonce, uri, challenge.algorithm, response
+        );
+        if let Some(qop) = challenge.qop {
+            header += &format!(", qop={}, nc={nc}, cnonce=\"{cnonce}\"", qop.as_str());
+        }
+        if let Some(opaque) = &challenge.opaque {
+            header += &format!(", opaque=\"{opaque}\"");
+        }
+        header
+    }
+
+    fn response(
+        &self,
+        challenge: &DigestChallenge,
+        method: &str,
+        uri: &str,
+        body: &[u8],
+        nc: &str,
+        cnonce: &str,
+    ) -> String {
+        let algorithm = challenge.algorithm;
+        let mut ha1 = algorithm.hash(&format!(
+            "{}:{}:{}",
+            self.username, challenge.realm, self.password
+        ));
+        if algorithm.is_session() {
+            ha1 = algorithm.hash(&format!("{ha1}:{}:{cnonce}", challenge.nonce));
+        }
+
+        let ha2 = match challenge.qop {
+            Some(Qop::AuthInt) => {
+                algorithm.hash(&format!("{method}:{uri}:{}", algorithm.hash_bytes(body)))
+            }
+            _ => algorithm.hash(&format!("{method}:{uri}")),
+        };
+
+        match challenge.qop {
+            Some(qop) => algorithm.hash(&format!(
+                "{ha1}:{}:{nc}:{cnonce}:{}:{ha2}",
+                challenge.nonce,
+                qop.as_str()
+            )),
+            None => algorithm.hash(&format!("{ha1}:{}:{ha2}", challenge.nonce)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DigestAlgorithm, DigestAuth, DigestChallenge, Qop};
+
+    // Example from RFC 7616 section 3.9.1
+    const CHALLENGE: &str = r#"realm="http-auth@example.org", qop="auth, auth-int", algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#;
+    const CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+    #[test]
+    fn test_parse_challenge() {
+        let challenge = DigestChallenge::parse(CHALLENGE).unwrap();
+        assert_eq!("http-auth@example.org", challenge.realm);
+        assert_eq!(DigestAlgorithm::Sha256, challenge.algorithm);
+        assert_eq!(Some(Qop::Auth), challenge.qop);
+    }
+
+    #[test]
+    fn test_rfc7616_sha256_response() {
+        let challenge = DigestChallenge::parse(CHALLENGE).unwrap();
+        let auth = DigestAuth::new("Mufasa", "Circle of Life");
+        let response = auth.response(&challenge, "GET", "/dir/index.html", &[], "00000001", CNONCE);
+        assert_eq!(
+            "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1",
+            response
+        );
+    }
+
+    #[test]
+    fn test_rfc7616_md5_response() {
+        let challenge = DigestChallenge {
+            algorithm: DigestAlgorithm::Md5,
+            ..DigestChallenge::parse(CHALLENGE).unwrap()
+        };
+        let auth = DigestAuth::new("Mufasa", "Circle of Life");
+        let response = auth.response(&challenge, "GET", "/dir/index.html", &[], "00000001", CNONCE);
+        assert_eq!("8ca523f5e9506fed4657c9700eebdbec", response);
+    }
+}
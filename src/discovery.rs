@@ -0,0 +1,129 @@
+use std::{
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use serde::Deserialize;
+
+use crate::error::{FetchError, FetchResult};
+
+/// Provides the base URLs of an `EndpointSet`, see `EndpointSet::discover_from`. Periodic checks
+/// run on the blocking thread pool, so `discover` may block on I/O
+pub trait DiscoverySource: Debug + Send + Sync {
+    /// Returns the current base URLs
+    fn discover(&self) -> FetchResult<Vec<String>>;
+}
+
+/// The contents of an endpoint file: either a plain list of base URLs, or a table with an
+/// `endpoints` list
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EndpointFile {
+    List(Vec<String>),
+    Table { endpoints: Vec<String> },
+}
+
+impl From<EndpointFile> for Vec<String> {
+    fn from(file: EndpointFile) -> Self {
+        match file {
+            EndpointFile::List(endpoints) | EndpointFile::Table { endpoints } => endpoints,
+        }
+    }
+}
+
+/// Reads base URLs from a JSON or TOML file, e.g. one written by a deployment during rollouts.
+///
+/// A JSON file contains either a list of URLs or an object with an `endpoints` list, a TOML file
+/// contains an `endpoints` list. Files ending in `.toml` are read as TOML, anything else as JSON.
+/// The file is only parsed again once its modification time changes.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use rust_fetch::{EndpointSet, Fetch, FileDiscovery};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let endpoints = EndpointSet::new(Vec::<String>::new());
+/// endpoints
+///     .discover_from(FileDiscovery::new("/etc/orders/upstreams.toml"), Duration::from_secs(5))
+///     .unwrap();
+/// let client = Fetch::from_endpoints(endpoints, None);
+/// assert!(client.is_ok());
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FileDiscovery {
+    path: PathBuf,
+    /// The modification time and contents of the file when it was last parsed
+    last: Mutex<Option<(SystemTime, Vec<String>)>>,
+}
+
+impl FileDiscovery {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            last: Default::default(),
+        }
+    }
+
+    fn error(&self, err: impl std::fmt::Display) -> FetchError {
+        FetchError::DiscoveryError(format!("{}: {err}", self.path.display()))
+    }
+
+    fn parse(&self, contents: &str) -> FetchResult<Vec<String>> {
+        let file: EndpointFile = match self.path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(contents).map_err(|e| self.error(e))?,
+            _ => serde_json::from_str(contents).map_err(|e| self.error(e))?,
+        };
+        Ok(file.into())
+    }
+}
+
+impl DiscoverySource for FileDiscovery {
+    fn discover(&self) -> FetchResult<Vec<String>> {
+        let modified = fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| self.error(e))?;
+        let mut last = self.last.lock().unwrap();
+        if let Some((parsed_at, endpoints)) = last.as_ref() {
+            if *parsed_at == modified {
+                return Ok(endpoints.clone());
+            }
+        }
+
+        let contents = fs::read_to_string(&self.path).map_err(|e| self.error(e))?;
+        let endpoints = self.parse(&contents)?;
+        *last = Some((modified, endpoints.clone()));
+        Ok(endpoints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileDiscovery;
+
+    #[test]
+    fn test_parse_json_and_toml() {
+        let json = FileDiscovery::new("upstreams.json");
+        assert_eq!(
+            vec!["http://a.local".to_string()],
+            json.parse(r#"["http://a.local"]"#).unwrap()
+        );
+        assert_eq!(
+            vec!["http://a.local".to_string(), "http://b.local".to_string()],
+            json.parse(r#"{"endpoints": ["http://a.local", "http://b.local"]}"#)
+                .unwrap()
+        );
+
+        let toml = FileDiscovery::new("upstreams.toml");
+        assert_eq!(
+            vec!["http://a.local".to_string()],
+            toml.parse(r#"endpoints = ["http://a.local"]"#).unwrap()
+        );
+        assert!(toml.parse("endpoints = ").is_err());
+    }
+}
@@ -0,0 +1,447 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    cache::{CacheStore, CachedResponse},
+    error::{FetchError, FetchResult},
+};
+
+const INDEX_FILE: &str = "index.json";
+const BLOB_DIR: &str = "blobs";
+/// How often the index is rewritten at most while entries change
+const PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    /// The cached response without its body
+    response: CachedResponse,
+    /// SHA-256 of the body, which is also the name of the blob file
+    body_hash: String,
+    size: u64,
+    last_used: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    entries: HashMap<String, IndexEntry>,
+    tick: u64,
+    /// How many entries reference each blob
+    #[serde(skip)]
+    references: HashMap<String, usize>,
+    /// The total size of the referenced blobs
+    #[serde(skip)]
+    size: u64,
+    /// True if the index changed since it was last persisted
+    #[serde(skip)]
+    dirty: bool,
+    #[serde(skip)]
+    persisted_at: Option<Instant>,
+}
+
+impl Index {
+    /// Parses a persisted index and counts the references to its blobs
+    fn parse(bytes: &[u8]) -> serde_json::Result<Self> {
+        let mut parsed: Self = serde_json::from_slice(bytes)?;
+        let mut index = Self {
+            tick: parsed.tick,
+            ..Default::default()
+        };
+        for (key, entry) in parsed.entries.drain() {
+            index.insert(key, entry);
+        }
+        index.dirty = false;
+        Ok(index)
+    }
+
+    fn insert(&mut self, key: String, entry: IndexEntry) {
+        let references = self.references.entry(entry.body_hash.clone()).or_default();
+        if *references == 0 {
+            self.size += entry.size;
+        }
+        *references += 1;
+        if let Some(replaced) = self.entries.insert(key, entry) {
+            self.release(&replaced);
+        }
+        self.dirty = true;
+    }
+
+    /// Removes the entry under `key`. Returns it if no other entry shares its blob
+    fn remove(&mut self, key: &str) -> Option<IndexEntry> {
+        let entry = self.entries.remove(key)?;
+        self.dirty = true;
+        self.release(&entry).then_some(entry)
+    }
+
+    /// Drops a reference to the blob of `entry`. Returns true if it was the last one
+    fn release(&mut self, entry: &IndexEntry) -> bool {
+        let Some(references) = self.references.get_mut(&entry.body_hash) else {
+            return false;
+        };
+        *references -= 1;
+        if *references > 0 {
+            return false;
+        }
+        self.references.remove(&entry.body_hash);
+        self.size -= entry.size;
+        true
+    }
+}
+
+/// A `CacheStore` that persists responses on disk so they survive restarts.
+///
+/// Bodies are stored content-addressed (named after their SHA-256) under `blobs/`, and an
+/// `index.json` maps cache keys to response metadata. Once the bodies exceed `max_bytes`, the
+/// least recently used entries are evicted.
+///
+/// Files are written to a temporary file and renamed into place, and bodies are checked against
+/// their hash when read, so a crash mid-write never serves a truncated body. The index is
+/// rewritten at most once a second while entries change, and when the store is dropped or
+/// `flush`ed. Blobs the index lost track of after a crash are removed when the cache is opened.
+///
+/// The store does blocking file I/O, so `HttpCache` runs it on tokio's blocking thread pool.
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use rust_fetch::{DiskCacheStore, Fetch, FetchConfig, HttpCache};
+///
+/// let dir = std::env::temp_dir().join("rust-fetch-doc-cache");
+/// let store = DiskCacheStore::new(&dir, 50 * 1024 * 1024).unwrap();
+/// let client = Fetch::new(
+///     "https://field-api.local",
+///     Some(FetchConfig {
+///         cache: Some(HttpCache::new(Arc::new(store)).with_offline_fallback(true)),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// ```
+#[derive(Debug)]
+pub struct DiskCacheStore {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<Index>,
+}
+
+impl DiskCacheStore {
+    /// Opens (or creates) a cache in `dir` holding at most `max_bytes` of response bodies.
+    ///
+    /// An unreadable index starts the cache over empty. Blobs no index entry refers to are
+    /// removed either way.
+    pub fn new(dir: impl AsRef<Path>, max_bytes: u64) -> FetchResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(dir.join(BLOB_DIR)).map_err(|e| cache_error(&dir, e))?;
+
+        let index = match fs::read(dir.join(INDEX_FILE)) {
+            Ok(bytes) => Index::parse(&bytes).unwrap_or_default(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Index::default(),
+            Err(e) => return Err(cache_error(&dir, e)),
+        };
+
+        let store = Self {
+            dir,
+            max_bytes,
+            index: Mutex::new(index),
+        };
+        store.remove_orphaned_blobs()?;
+        Ok(store)
+    }
+
+    /// The total size of the stored bodies in bytes
+    pub fn size(&self) -> u64 {
+        self.index.lock().unwrap().size
+    }
+
+    /// Writes pending changes of the index to disk
+    pub fn flush(&self) -> FetchResult<()> {
+        let mut index = self.index.lock().unwrap();
+        if !index.dirty {
+            return Ok(());
+        }
+        self.persist(&mut index)
+            .map_err(|e| cache_error(&self.dir, e))
+    }
+
+    fn blob_path(&self, body_hash: &str) -> PathBuf {
+        self.dir.join(BLOB_DIR).join(body_hash)
+    }
+
+    /// Removes blobs (and leftover temporary files) that no entry refers to
+    fn remove_orphaned_blobs(&self) -> FetchResult<()> {
+        let index = self.index.lock().unwrap();
+        let blob_dir = self.dir.join(BLOB_DIR);
+        for file in fs::read_dir(&blob_dir).map_err(|e| cache_error(&blob_dir, e))? {
+            let file = file.map_err(|e| cache_error(&blob_dir, e))?;
+            let name = file.file_name();
+            if !index
+                .references
+                .contains_key(name.to_string_lossy().as_ref())
+            {
+                let _ = fs::remove_file(file.path());
+            }
+        }
+        Ok(())
+    }
+
+    fn persist(&self, index: &mut Index) -> io::Result<()> {
+        write_atomically(&self.dir.join(INDEX_FILE), &serde_json::to_vec(index)?)?;
+        index.dirty = false;
+        index.persisted_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Persists a changed index, unless it was persisted less than `PERSIST_INTERVAL` ago
+    fn persist_changes(&self, index: &mut Index) {
+        let recently_persisted = index
+            .persisted_at
+            .is_some_and(|persisted_at| persisted_at.elapsed() < PERSIST_INTERVAL);
+        if index.dirty && !recently_persisted {
+            let _ = self.persist(index);
+        }
+    }
+
+    /// Reads the body of `entry`, or `None` if its blob is missing or does not match its hash
+    fn read_blob(&self, entry: &IndexEntry) -> Option<Vec<u8>> {
+        let body = fs::read(self.blob_path(&entry.body_hash)).ok()?;
+        if body.len() as u64 != entry.size || hex::encode(Sha256::digest(&body)) != entry.body_hash
+        {
+            let _ = fs::remove_file(self.blob_path(&entry.body_hash));
+            return None;
+        }
+        Some(body)
+    }
+
+    fn remove_entry(&self, index: &mut Index, key: &str) {
+        if let Some(entry) = index.remove(key) {
+            let _ = fs::remove_file(self.blob_path(&entry.body_hash));
+        }
+    }
+
+    fn evict(&self, index: &mut Index) {
+        while index.size > self.max_bytes {
+            let Some(oldest) = index
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove_entry(index, &oldest);
+        }
+    }
+}
+
+impl CacheStore for DiskCacheStore {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut index = self.index.lock().unwrap();
+        index.tick += 1;
+        let tick = index.tick;
+
+        let entry = index.entries.get_mut(key)?;
+        entry.last_used = tick;
+        let entry = entry.clone();
+        index.dirty = true;
+
+        let cached = match self.read_blob(&entry) {
+            Some(body) => Some(CachedResponse {
+                body,
+                ..entry.response
+            }),
+            None => {
+                // The blob was removed behind our back or is corrupt, forget the entry
+                self.remove_entry(&mut index, key);
+                None
+            }
+        };
+        self.persist_changes(&mut index);
+        cached
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) {
+        let size = response.body.len() as u64;
+        if size > self.max_bytes {
+            return;
+        }
+        let body_hash = hex::encode(Sha256::digest(&response.body));
+
+        let mut index = self.index.lock().unwrap();
+        self.remove_entry(&mut index, key);
+        let blob_path = self.blob_path(&body_hash);
+        let intact = fs::metadata(&blob_path).is_ok_and(|metadata| metadata.len() == size);
+        if !intact && write_atomically(&blob_path, &response.body).is_err() {
+            self.persist_changes(&mut index);
+            return;
+        }
+        index.tick += 1;
+        let last_used = index.tick;
+        index.insert(
+            key.to_string(),
+            IndexEntry {
+                response: CachedResponse {
+                    body: Vec::new(),
+                    ..response
+                },
+                body_hash,
+                size,
+                last_used,
+            },
+        );
+        self.evict(&mut index);
+        self.persist_changes(&mut index);
+    }
+
+    fn remove(&self, key: &str) {
+        let mut index = self.index.lock().unwrap();
+        self.remove_entry(&mut index, key);
+        self.persist_changes(&mut index);
+    }
+
+    fn is_blocking(&self) -> bool {
+        true
+    }
+}
+
+impl Drop for DiskCacheStore {
+    fn drop(&mut self) {
+        // A poisoned index is left as it was last persisted
+        if let Ok(mut index) = self.index.lock() {
+            if index.dirty {
+                let _ = self.persist(&mut index);
+            }
+        }
+    }
+}
+
+/// Writes `contents` to a temporary file and renames it to `path`, so a crash never leaves a torn
+/// file behind
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(tmp, path)
+}
+
+fn cache_error(dir: &Path, err: io::Error) -> FetchError {
+    FetchError::CacheError(format!("{}: {err}", dir.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::DiskCacheStore;
+    use crate::cache::{CacheStore, CachedResponse};
+
+    fn response(body: &[u8]) -> CachedResponse {
+        CachedResponse {
+            status: 200,
+            headers: vec![("etag".to_string(), "\"v1\"".to_string())],
+            body: body.to_vec(),
+            stored_at: SystemTime::now(),
+            vary: Vec::new(),
+        }
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("rust-fetch-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_entries_survive_reopening() {
+        let dir = temp_dir("reopen");
+        DiskCacheStore::new(&dir, 1024)
+            .unwrap()
+            .put("GET http://localhost/a", response(b"hello"));
+
+        let reopened = DiskCacheStore::new(&dir, 1024).unwrap();
+        let cached = reopened.get("GET http://localhost/a").unwrap();
+        assert_eq!(b"hello".to_vec(), cached.body);
+        assert_eq!("\"v1\"", cached.header("etag").unwrap());
+    }
+
+    #[test]
+    fn test_identical_bodies_share_a_blob() {
+        let dir = temp_dir("dedup");
+        let store = DiskCacheStore::new(&dir, 1024).unwrap();
+        store.put("a", response(b"same"));
+        store.put("b", response(b"same"));
+        assert_eq!(4, store.size());
+
+        store.remove("a");
+        assert_eq!(b"same".to_vec(), store.get("b").unwrap().body);
+    }
+
+    #[test]
+    fn test_least_recently_used_entries_are_evicted() {
+        let dir = temp_dir("evict");
+        let store = DiskCacheStore::new(&dir, 10).unwrap();
+        store.put("a", response(b"aaaa"));
+        store.put("b", response(b"bbbb"));
+        store.get("a");
+        store.put("c", response(b"cccc"));
+
+        assert!(store.get("a").is_some());
+        assert!(store.get("b").is_none());
+        assert!(store.get("c").is_some());
+        assert!(store.size() <= 10);
+    }
+
+    #[test]
+    fn test_recent_use_survives_reopening() {
+        let dir = temp_dir("recency");
+        let store = DiskCacheStore::new(&dir, 10).unwrap();
+        store.put("a", response(b"aaaa"));
+        store.put("b", response(b"bbbb"));
+        store.get("a");
+        drop(store);
+
+        let reopened = DiskCacheStore::new(&dir, 10).unwrap();
+        reopened.put("c", response(b"cccc"));
+        assert!(reopened.get("a").is_some());
+        assert!(reopened.get("b").is_none());
+        assert_eq!(8, reopened.size());
+    }
+
+    #[test]
+    fn test_corrupt_index_removes_orphaned_blobs() {
+        let dir = temp_dir("corrupt");
+        DiskCacheStore::new(&dir, 1024)
+            .unwrap()
+            .put("a", response(b"hello"));
+        std::fs::write(dir.join("index.json"), b"{ not json").unwrap();
+
+        let reopened = DiskCacheStore::new(&dir, 1024).unwrap();
+        assert!(reopened.get("a").is_none());
+        assert_eq!(0, reopened.size());
+        assert_eq!(0, std::fs::read_dir(dir.join("blobs")).unwrap().count());
+    }
+
+    #[test]
+    fn test_truncated_blob_is_not_served_and_rewritten() {
+        let dir = temp_dir("truncated");
+        let store = DiskCacheStore::new(&dir, 1024).unwrap();
+        store.put("a", response(b"hello world"));
+        let blob = std::fs::read_dir(dir.join("blobs"))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        std::fs::write(&blob, b"hello").unwrap();
+
+        assert!(store.get("a").is_none());
+        store.put("a", response(b"hello world"));
+        assert_eq!(b"hello world".to_vec(), store.get("a").unwrap().body);
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rand::Rng;
+use reqwest::{Request, Url};
+use tokio::time::Instant;
+
+use crate::{
+    discovery::DiscoverySource,
+    error::{FetchError, FetchResult},
+};
+
+/// How `EndpointSet` picks the base URL for a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionStrategy {
+    /// Cycle through the endpoints in order
+    #[default]
+    RoundRobin,
+    /// Pick an endpoint at random
+    Random,
+    /// Pick the endpoint with the fewest requests in flight
+    LeastOutstanding,
+    /// Send everything to the first healthy endpoint, in the order they were given
+    PrimaryFailover,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    outstanding: usize,
+    consecutive_failures: u32,
+    /// Set while the endpoint is ejected: no requests are sent to it before then, after which a
+    /// single probe is let through
+    ejected_until: Option<Instant>,
+}
+
+impl Endpoint {
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            outstanding: 0,
+            consecutive_failures: 0,
+            ejected_until: None,
+        }
+    }
+
+    /// The part of a request url this endpoint's base url covers
+    fn prefix(&self) -> &str {
+        self.url.trim_end_matches('/')
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    endpoints: Vec<Endpoint>,
+    next: usize,
+}
+
+impl State {
+    /// Replaces the endpoints, keeping the health of the ones that stay
+    fn replace(&mut self, urls: Vec<String>) {
+        let mut previous = std::mem::take(&mut self.endpoints);
+        self.endpoints = urls
+            .into_iter()
+            .map(|url| match previous.iter().position(|e| e.url == url) {
+                Some(index) => previous.swap_remove(index),
+                None => Endpoint::new(url),
+            })
+            .collect();
+    }
+}
+
+/// A set of base URLs for replicas of the same service, used instead of a single base URL.
+///
+/// Every request goes to the endpoint picked by the `SelectionStrategy`. Connection errors and
+/// `5xx` responses count as failures: after `max_failures` consecutive failures an endpoint is
+/// ejected for `ejection_time`, after which a single probe request is sent to it. If the probe
+/// succeeds the endpoint is back in rotation, otherwise it is ejected again. When every endpoint
+/// is ejected, requests are spread over all of them. Retries go to a different endpoint where
+/// possible.
+///
+/// Clones share their endpoints and health.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rust_fetch::{EndpointSet, Fetch, SelectionStrategy};
+///
+/// let endpoints = EndpointSet::new(["http://orders-1.local", "http://orders-2.local"])
+///     .with_strategy(SelectionStrategy::LeastOutstanding)
+///     .with_ejection(3, Duration::from_secs(10));
+/// let client = Fetch::from_endpoints(endpoints, None);
+/// assert!(client.is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct EndpointSet {
+    strategy: SelectionStrategy,
+    max_failures: u32,
+    ejection_time: Duration,
+    state: Arc<Mutex<State>>,
+}
+
+impl EndpointSet {
+    /// Round-robins over `urls`, ejecting an endpoint for 30 seconds after 5 consecutive failures
+    pub fn new<I, S>(urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            strategy: Default::default(),
+            max_failures: 5,
+            ejection_time: Duration::from_secs(30),
+            state: Arc::new(Mutex::new(State {
+                endpoints: urls
+                    .into_iter()
+                    .map(|url| Endpoint::new(url.into()))
+                    .collect(),
+                next: 0,
+            })),
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: SelectionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Ejects an endpoint for `ejection_time` after `max_failures` consecutive failures
+    pub fn with_ejection(mut self, max_failures: u32, ejection_time: Duration) -> Self {
+        self.max_failures = max_failures.max(1);
+        self.ejection_time = ejection_time;
+        self
+    }
+
+    /// All base URLs in the set
+    pub fn urls(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state.endpoints.iter().map(|e| e.url.clone()).collect()
+    }
+
+    /// The base URLs that are not currently ejected
+    pub fn healthy(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .endpoints
+            .iter()
+            .filter(|e| e.ejected_until.is_none())
+            .map(|e| e.url.clone())
+            .collect()
+    }
+
+    /// Replaces the base URLs. Endpoints that stay in the set keep their health, and requests
+    /// already sent to removed endpoints complete normally
+    pub fn set_urls<I, S>(&self, urls: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let urls = urls.into_iter().map(Into::into).collect();
+        self.state.lock().unwrap().replace(urls);
+    }
+
+    /// Keeps the base URLs in sync with `source`, checking it every `interval` on a background
+    /// task for as long as the set is in use. Must be called within a Tokio runtime.
+    ///
+    /// Fails if `source` cannot be read right away. Later errors and empty results keep the last
+    /// known URLs, so a half-written file does not take the client down.
+    pub fn discover_from<D>(&self, source: D, interval: Duration) -> FetchResult<()>
+    where
+        D: DiscoverySource + 'static,
+    {
+        self.set_urls(source.discover()?);
+
+        // Sources read files or ask other services synchronously, so later checks run on the
+        // blocking thread pool instead of stalling the runtime
+        let source = Arc::new(source);
+        let state = Arc::downgrade(&self.state);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval.max(Duration::from_millis(1)));
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(state) = state.upgrade() else {
+                    return;
+                };
+                let source = source.clone();
+                match tokio::task::spawn_blocking(move || source.discover()).await {
+                    Ok(Ok(urls)) if !urls.is_empty() => state.lock().unwrap().replace(urls),
+                    _ => {}
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn pick(&self, excluded: Option<&str>, now: Instant) -> FetchResult<String> {
+        let mut state = self.state.lock().unwrap();
+        let State { endpoints, next } = &mut *state;
+        if endpoints.is_empty() {
+            return Err(FetchError::NoEndpoints);
+        }
+
+        // An ejected endpoint whose time is up gets the next request as its probe
+        if let Some(endpoint) = endpoints
+            .iter_mut()
+            .find(|e| e.ejected_until.is_some_and(|until| until <= now))
+        {
+            endpoint.ejected_until = Some(now + self.ejection_time);
+            return Ok(endpoint.url.clone());
+        }
+
+        let healthy = |e: &&Endpoint| e.ejected_until.is_none();
+        let mut candidates: Vec<&Endpoint> = endpoints.iter().filter(healthy).collect();
+        if candidates.is_empty() {
+            candidates = endpoints.iter().collect();
+        }
+        if candidates.len() > 1 {
+            if let Some(excluded) = excluded {
+                candidates.retain(|e| e.url != excluded);
+            }
+        }
+
+        let start = *next % candidates.len();
+        *next = next.wrapping_add(1);
+        let endpoint = match self.strategy {
+            SelectionStrategy::RoundRobin => candidates[start],
+            SelectionStrategy::Random => {
+                candidates[rand::thread_rng().gen_range(0..candidates.len())]
+            }
+            SelectionStrategy::LeastOutstanding => candidates
+                .iter()
+                .cycle()
+                .skip(start)
+                .take(candidates.len())
+                .min_by_key(|e| e.outstanding)
+                .copied()
+                .unwrap_or(candidates[start]),
+            SelectionStrategy::PrimaryFailover => candidates[0],
+        };
+        Ok(endpoint.url.clone())
+    }
+
+    /// Picks the base URL for a new request
+    pub(crate) fn select(&self) -> FetchResult<String> {
+        self.pick(None, Instant::now())
+    }
+
+    /// Points `request` at another endpoint before it is retried
+    pub(crate) fn reroute(&self, request: &mut Request) {
+        let url = request.url().to_string();
+        let Some(current) = self.endpoint_of(&url) else {
+            return;
+        };
+        let Ok(base) = self.pick(Some(&current), Instant::now()) else {
+            return;
+        };
+        let rerouted = format!(
+            "{}{}",
+            base.trim_end_matches('/'),
+            &url[current.trim_end_matches('/').len()..]
+        );
+        if let Ok(rerouted) = Url::parse(&rerouted) {
+            *request.url_mut() = rerouted;
+        }
+    }
+
+    /// The part of `url` after the base URL of its endpoint, the same on every replica
+    pub(crate) fn relative<'a>(&self, url: &'a str) -> Option<&'a str> {
+        let base = self.endpoint_of(url)?;
+        Some(&url[base.trim_end_matches('/').len()..])
+    }
+
+    /// The base URL of the endpoint `url` belongs to
+    fn endpoint_of(&self, url: &str) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .endpoints
+            .iter()
+            .filter(|e| url.starts_with(e.prefix()))
+            .max_by_key(|e| e.prefix().len())
+            .map(|e| e.url.clone())
+    }
+
+    /// Counts a request to `url` as in flight until the returned guard is dropped
+    pub(crate) fn begin(&self, url: &Url) -> Option<InFlight> {
+        let base = self.endpoint_of(url.as_str())?;
+        let mut state = self.state.lock().unwrap();
+        let endpoint = state.endpoints.iter_mut().find(|e| e.url == base)?;
+        endpoint.outstanding += 1;
+        Some(InFlight {
+            endpoints: self.clone(),
+            base,
+        })
+    }
+
+    fn record(&self, base: &str, failed: bool, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        let Some(endpoint) = state.endpoints.iter_mut().find(|e| e.url == base) else {
+            return;
+        };
+        if !failed {
+            endpoint.consecutive_failures = 0;
+            endpoint.ejected_until = None;
+            return;
+        }
+        endpoint.consecutive_failures += 1;
+        if endpoint.ejected_until.is_some() || endpoint.consecutive_failures >= self.max_failures {
+            endpoint.ejected_until = Some(now + self.ejection_time);
+        }
+    }
+}
+
+/// A request in flight to one of the endpoints of an `EndpointSet`
+pub(crate) struct InFlight {
+    endpoints: EndpointSet,
+    base: String,
+}
+
+impl InFlight {
+    /// Records whether the request failed
+    pub(crate) fn finish(self, failed: bool) {
+        self.endpoints.record(&self.base, failed, Instant::now());
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut state = self.endpoints.state.lock().unwrap();
+        if let Some(endpoint) = state.endpoints.iter_mut().find(|e| e.url == self.base) {
+            endpoint.outstanding = endpoint.outstanding.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::Url;
+    use tokio::time::Instant;
+
+    use super::{EndpointSet, SelectionStrategy};
+
+    #[test]
+    fn test_ejected_endpoint_is_probed_and_returns() {
+        let endpoints = EndpointSet::new(["http://a.local", "http://b.local"])
+            .with_strategy(SelectionStrategy::PrimaryFailover)
+            .with_ejection(2, Duration::from_secs(10));
+        let now = Instant::now();
+
+        endpoints.record("http://a.local", true, now);
+        assert_eq!("http://a.local", endpoints.pick(None, now).unwrap());
+        endpoints.record("http://a.local", true, now);
+        assert_eq!("http://b.local", endpoints.pick(None, now).unwrap());
+        assert_eq!(vec!["http://b.local".to_string()], endpoints.healthy());
+
+        // The probe fails, so the endpoint stays ejected for another 10 seconds
+        let later = now + Duration::from_secs(10);
+        assert_eq!("http://a.local", endpoints.pick(None, later).unwrap());
+        assert_eq!("http://b.local", endpoints.pick(None, later).unwrap());
+        endpoints.record("http://a.local", true, later);
+
+        let even_later = later + Duration::from_secs(10);
+        assert_eq!("http://a.local", endpoints.pick(None, even_later).unwrap());
+        endpoints.record("http://a.local", false, even_later);
+        assert_eq!(2, endpoints.healthy().len());
+        assert_eq!("http://a.local", endpoints.pick(None, even_later).unwrap());
+    }
+
+    #[test]
+    fn test_least_outstanding_and_exclusion() {
+        let endpoints = EndpointSet::new(["http://a.local/api", "http://b.local/api"])
+            .with_strategy(SelectionStrategy::LeastOutstanding);
+        let now = Instant::now();
+
+        let _in_flight = endpoints.begin(&Url::parse("http://a.local/api/orders").unwrap());
+        for _ in 0..3 {
+            assert_eq!("http://b.local/api", endpoints.pick(None, now).unwrap());
+        }
+        assert_eq!(
+            "http://a.local/api",
+            endpoints.pick(Some("http://b.local/api"), now).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_robin_and_empty_set() {
+        let endpoints = EndpointSet::new(["http://a.local", "http://b.local"]);
+        let now = Instant::now();
+        assert_eq!("http://a.local", endpoints.pick(None, now).unwrap());
+        assert_eq!("http://b.local", endpoints.pick(None, now).unwrap());
+        assert_eq!("http://a.local", endpoints.pick(None, now).unwrap());
+
+        assert!(EndpointSet::new(Vec::<String>::new())
+            .pick(None, now)
+            .is_err());
+    }
+}
@@ -1,3 +1,6 @@
+use std::{fmt::Display, time::Duration};
+
+use reqwest::StatusCode;
 use thiserror::Error;
 
 use crate::network_error::NetworkError;
@@ -30,6 +33,72 @@ pub enum SerializationError {
     Unknown(String),
 }
 
+/// Represents an error that occurs while obtaining an OAuth2 access token
+#[derive(Error, Debug)]
+pub enum OAuth2Error {
+    #[error("Token endpoint returned {status}: {error}")]
+    TokenEndpoint {
+        status: StatusCode,
+        error: String,
+        description: Option<String>,
+    },
+    #[error("Unable to reach token endpoint: {0}")]
+    Request(reqwest::Error),
+    #[error(transparent)]
+    InvalidTokenResponse(serde_json::Error),
+    #[error("No refresh token is available")]
+    NoRefreshToken,
+    #[error("The device code expired before the user authorized the request")]
+    DeviceCodeExpired,
+    #[error("Unsupported token type {0}, only bearer tokens can be sent")]
+    UnsupportedTokenType(String),
+}
+
+/// Represents an invalid `FetchBuilder` configuration
+#[derive(Error, Debug)]
+pub enum BuildError {
+    #[error("Either a base url or an endpoint set is required")]
+    MissingBaseUrl,
+    #[error("{0} is not a valid base url")]
+    InvalidBaseUrl(String),
+    #[error("Invalid default header {0}: {1}")]
+    InvalidHeader(String, String),
+    #[error("Invalid proxy {0}: {1}")]
+    InvalidProxy(String, String),
+    #[error("Invalid root certificate: {0}")]
+    InvalidCertificate(String),
+    #[error("Invalid timeouts: {0}")]
+    InvalidTimeout(String),
+    #[error("{0} and {1} cannot be combined")]
+    ConflictingOptions(&'static str, &'static str),
+    #[error("Unable to build the HTTP client: {0}")]
+    Client(reqwest::Error),
+}
+
+/// The phase of a call whose timeout expired
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutPhase {
+    /// Establishing the connection (`FetchConfig::connect_timeout_ms`)
+    Connect,
+    /// Waiting for the next bytes of the response (`FetchConfig::read_timeout_ms`)
+    Read,
+    /// A single attempt, from sending the request to reading its body (`timeout_ms`)
+    Request,
+    /// The whole call, including retries, backoff and queueing (`deadline_ms`)
+    Deadline,
+}
+
+impl Display for TimeoutPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeoutPhase::Connect => write!(f, "Connect"),
+            TimeoutPhase::Read => write!(f, "Read"),
+            TimeoutPhase::Request => write!(f, "Request"),
+            TimeoutPhase::Deadline => write!(f, "Deadline"),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum FetchError {
     #[error(transparent)]
@@ -40,10 +109,90 @@ pub enum FetchError {
     InvalidUrl(String),
     #[error("Request failed. StatusCode: {:?}", err.status())]
     UnableToSendRequest { err: reqwest::Error },
+    #[error("{phase} timeout of {after:?} expired")]
+    Timeout { phase: TimeoutPhase, after: Duration },
+    #[error("The call was cancelled")]
+    Cancelled,
     #[error(transparent)]
     NetworkError(NetworkError),
     #[error(transparent)]
     SerializationError(SerializationError),
     #[error(transparent)]
     DeserializationError(DeserializationError),
+    #[error(transparent)]
+    OAuth2(OAuth2Error),
+    #[error(transparent)]
+    Build(BuildError),
+    #[error("Unable to sign request: {0}")]
+    SigningError(String),
+    /// A `Middleware` rejected a request or response
+    #[error("Middleware error: {0}")]
+    MiddlewareError(String),
+    #[error("Invalid message signature: {0}")]
+    InvalidSignature(String),
+    #[error("Response body does not match its {header} ({algorithm})")]
+    DigestMismatch { header: String, algorithm: String },
+    #[error("Cache error: {0}")]
+    CacheError(String),
+    /// The server rejected a conditional request (`412 Precondition Failed`), usually because the
+    /// resource was modified concurrently. `etag` is the current ETag if the server sent one.
+    #[error("Precondition failed, the resource was modified (current ETag: {etag:?})")]
+    PreconditionFailed { etag: Option<String>, error: NetworkError },
+    #[error("The resource has no strong ETag or Last-Modified to make a conditional request with")]
+    MissingValidator,
+    #[error("Rate limit exceeded for {key:?}, retry after {retry_after:?}")]
+    RateLimited { key: String, retry_after: Duration },
+    #[error("Circuit for {host} is open, retry after {retry_after:?}")]
+    CircuitOpen { host: String, retry_after: Duration },
+    #[error("Bulkhead for {key} is full")]
+    BulkheadFull { key: String },
+    #[error("The endpoint set has no base URLs")]
+    NoEndpoints,
+    #[error("Service discovery failed: {0}")]
+    DiscoveryError(String),
+    /// A failed call was not retried because the `RetryBudget` ran out. `attempts` counts the
+    /// attempts made, `source` is the error of the last one
+    #[error("Retry budget exhausted after {attempts} attempt(s): {source}")]
+    RetryBudgetExhausted {
+        attempts: u32,
+        source: Box<FetchError>,
+    },
+    /// Wraps the error of a request that was sent with an `Idempotency-Key`, so the key can be
+    /// logged and the request replayed with it. Use `cause` or `into_inner` to get at the
+    /// underlying error
+    #[error("{source} (Idempotency-Key: {key})")]
+    Idempotent {
+        key: String,
+        source: Box<FetchError>,
+    },
+}
+
+impl FetchError {
+    /// The `Idempotency-Key` of the failed request, if it was sent with one
+    pub fn idempotency_key(&self) -> Option<&str> {
+        match self {
+            FetchError::Idempotent { key, .. } => Some(key),
+            _ => None,
+        }
+    }
+
+    /// The error that caused the failure, looking through the `Idempotent` and
+    /// `RetryBudgetExhausted` wrappers, e.g. to match on a `NetworkError` whether or not the
+    /// request carried an `Idempotency-Key`
+    pub fn cause(&self) -> &FetchError {
+        match self {
+            FetchError::Idempotent { source, .. }
+            | FetchError::RetryBudgetExhausted { source, .. } => source.cause(),
+            err => err,
+        }
+    }
+
+    /// The underlying error, with the `Idempotent` and `RetryBudgetExhausted` wrappers removed
+    pub fn into_inner(self) -> FetchError {
+        match self {
+            FetchError::Idempotent { source, .. }
+            | FetchError::RetryBudgetExhausted { source, .. } => source.into_inner(),
+            err => err,
+        }
+    }
 }
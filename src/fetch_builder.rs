@@ -0,0 +1,449 @@
+use std::{sync::Arc, time::Duration};
+
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    redirect, Certificate, ClientBuilder, Proxy, Url,
+};
+
+use crate::{
+    error::{BuildError, FetchError, FetchResult},
+    utils::millis_rounded_up,
+    ContentType, EndpointSet, Fetch, FetchAuth, FetchConfig, Middleware, RequestSigner,
+};
+
+/// Builds a `Fetch` with access to the connection settings `Fetch::new` does not expose.
+///
+/// Settings are validated by `build`, which reports problems as `FetchError::Build`.
+///
+/// Requests and responses can be inspected and changed with a chain of `Middleware`, see
+/// `middleware`.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rust_fetch::{reqwest::redirect::Policy, Fetch};
+///
+/// let client = Fetch::builder()
+///     .base_url("https://api.local/v1")
+///     .header("x-tenant", "acme")
+///     .timeout(Duration::from_secs(10))
+///     .connect_timeout(Duration::from_secs(2))
+///     .redirect(Policy::limited(3))
+///     .pool_max_idle_per_host(8)
+///     .gzip(true)
+///     .build();
+/// assert!(client.is_ok());
+/// ```
+#[derive(Debug, Default)]
+pub struct FetchBuilder {
+    base_url: Option<String>,
+    config: FetchConfig,
+    proxies: Vec<Proxy>,
+    no_proxy: bool,
+    root_certificates: Vec<Certificate>,
+    accept_invalid_certs: bool,
+    redirect: Option<redirect::Policy>,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    http1_only: bool,
+    http2_prior_knowledge: bool,
+    http2_keep_alive_interval: Option<Duration>,
+    /// Decompression settings, reqwest's defaults (all on) unless set
+    gzip: Option<bool>,
+    brotli: Option<bool>,
+    deflate: Option<bool>,
+    /// The first invalid setting, reported by `build`
+    error: Option<BuildError>,
+}
+
+impl FetchBuilder {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    fn fail(mut self, error: BuildError) -> Self {
+        self.error.get_or_insert(error);
+        self
+    }
+
+    /// The url all endpoints are joined with
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Spreads requests over several base urls instead of a single one
+    pub fn endpoints(mut self, endpoints: EndpointSet) -> Self {
+        self.config.endpoints = Some(endpoints);
+        self
+    }
+
+    /// Replaces all `FetchConfig` settings made so far, e.g. to reuse a config across clients
+    pub fn config(mut self, config: FetchConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Adds a header sent with every request
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        self.config
+            .headers
+            .get_or_insert_with(Default::default)
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn accept(mut self, accept: ContentType) -> Self {
+        self.config.accept = accept;
+        self
+    }
+
+    pub fn content_type(mut self, content_type: ContentType) -> Self {
+        self.config.content_type = content_type;
+        self
+    }
+
+    /// How long a single attempt may take, from sending the request to reading its body
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout_ms = Some(millis_rounded_up(timeout));
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout_ms = Some(millis_rounded_up(timeout));
+        self
+    }
+
+    /// How long to wait for the next bytes of a response
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.config.read_timeout_ms = Some(millis_rounded_up(timeout));
+        self
+    }
+
+    /// How long a whole call may take, including retries
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.config.deadline_ms = Some(millis_rounded_up(deadline));
+        self
+    }
+
+    pub fn auth(mut self, auth: FetchAuth) -> Self {
+        self.config.auth = Some(auth);
+        self
+    }
+
+    /// Adds `middleware` to the end of the chain every request and response passes through
+    pub fn middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.config.middleware.push(middleware);
+        self
+    }
+
+    /// Runs `signer` on every request right before it is sent, after all middleware
+    pub fn signer(mut self, signer: Arc<dyn RequestSigner>) -> Self {
+        self.config.signer = Some(signer);
+        self
+    }
+
+    /// Sends all requests through the proxy at `url`
+    pub fn proxy(mut self, url: &str) -> Self {
+        match Proxy::all(url) {
+            Ok(proxy) => {
+                self.proxies.push(proxy);
+                self
+            }
+            Err(e) => self.fail(BuildError::InvalidProxy(url.to_string(), e.to_string())),
+        }
+    }
+
+    /// Ignores the proxies configured in the environment
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+
+    /// Trusts the PEM encoded certificate `pem` in addition to the system's root certificates
+    pub fn add_root_certificate_pem(mut self, pem: &[u8]) -> Self {
+        match Certificate::from_pem(pem) {
+            Ok(certificate) => {
+                self.root_certificates.push(certificate);
+                self
+            }
+            Err(e) => self.fail(BuildError::InvalidCertificate(e.to_string())),
+        }
+    }
+
+    /// Accepts any server certificate. Only meant for tests against self-signed servers
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Which redirects are followed. Up to 10 are followed by default
+    pub fn redirect(mut self, policy: redirect::Policy) -> Self {
+        self.redirect = Some(policy);
+        self
+    }
+
+    /// How long idle connections are kept in the pool
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    pub fn http1_only(mut self) -> Self {
+        self.http1_only = true;
+        self
+    }
+
+    /// Speaks HTTP/2 right away, without negotiating it first
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.http2_prior_knowledge = true;
+        self
+    }
+
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Asks for and transparently decompresses gzip encoded responses (on by default). Cannot be
+    /// enabled together with `FetchConfig::verify_digests`
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.gzip = Some(enable);
+        self
+    }
+
+    /// Asks for and transparently decompresses brotli encoded responses (on by default)
+    pub fn brotli(mut self, enable: bool) -> Self {
+        self.brotli = Some(enable);
+        self
+    }
+
+    /// Asks for and transparently decompresses deflate encoded responses (on by default)
+    pub fn deflate(mut self, enable: bool) -> Self {
+        self.deflate = Some(enable);
+        self
+    }
+
+    fn validate(&self) -> Result<(), BuildError> {
+        match &self.base_url {
+            Some(base_url) => {
+                let url = Url::parse(base_url)
+                    .map_err(|_| BuildError::InvalidBaseUrl(base_url.clone()))?;
+                if !matches!(url.scheme(), "http" | "https") {
+                    return Err(BuildError::InvalidBaseUrl(base_url.clone()));
+                }
+            }
+            None if self.config.endpoints.is_none() => return Err(BuildError::MissingBaseUrl),
+            None => {}
+        }
+
+        for (key, value) in self.config.headers.iter().flatten() {
+            HeaderName::from_bytes(key.as_bytes())
+                .map_err(|e| BuildError::InvalidHeader(key.clone(), e.to_string()))?;
+            HeaderValue::from_str(value)
+                .map_err(|e| BuildError::InvalidHeader(key.clone(), e.to_string()))?;
+        }
+
+        let config = &self.config;
+        let timeouts = [
+            ("timeout", config.timeout_ms),
+            ("connect timeout", config.connect_timeout_ms),
+            ("read timeout", config.read_timeout_ms),
+            ("deadline", config.deadline_ms),
+        ];
+        if let Some((name, _)) = timeouts.iter().find(|(_, ms)| *ms == Some(0)) {
+            return Err(BuildError::InvalidTimeout(format!(
+                "the {name} must not be zero"
+            )));
+        }
+        if let (Some(connect), Some(timeout)) = (config.connect_timeout_ms, config.timeout_ms) {
+            if connect > timeout {
+                return Err(BuildError::InvalidTimeout(
+                    "the connect timeout exceeds the timeout".to_string(),
+                ));
+            }
+        }
+        if let (Some(timeout), Some(deadline)) = (config.timeout_ms, config.deadline_ms) {
+            if timeout > deadline {
+                return Err(BuildError::InvalidTimeout(
+                    "the timeout exceeds the deadline".to_string(),
+                ));
+            }
+        }
+
+        let decompression = [
+            ("gzip", self.gzip),
+            ("brotli", self.brotli),
+            ("deflate", self.deflate),
+        ];
+        if let Some((name, _)) = decompression
+            .iter()
+            .find(|(_, enabled)| config.verify_digests && *enabled == Some(true))
+        {
+            return Err(BuildError::ConflictingOptions("verify_digests", name));
+        }
+        if self.http1_only && self.http2_prior_knowledge {
+            return Err(BuildError::ConflictingOptions(
+                "http1_only",
+                "http2_prior_knowledge",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validates the settings and builds the client
+    pub fn build(mut self) -> FetchResult<Fetch> {
+        if let Some(error) = self.error.take() {
+            return Err(FetchError::Build(error));
+        }
+        self.validate().map_err(FetchError::Build)?;
+
+        let mut client =
+            ClientBuilder::default().danger_accept_invalid_certs(self.accept_invalid_certs);
+        if let Some(enable) = self.gzip {
+            client = client.gzip(enable);
+        }
+        if let Some(enable) = self.brotli {
+            client = client.brotli(enable);
+        }
+        if let Some(enable) = self.deflate {
+            client = client.deflate(enable);
+        }
+        if self.no_proxy {
+            client = client.no_proxy();
+        }
+        for proxy in self.proxies {
+            client = client.proxy(proxy);
+        }
+        for certificate in self.root_certificates {
+            client = client.add_root_certificate(certificate);
+        }
+        if let Some(policy) = self.redirect {
+            client = client.redirect(policy);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            client = client.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            client = client.pool_max_idle_per_host(max);
+        }
+        if self.http1_only {
+            client = client.http1_only();
+        }
+        if self.http2_prior_knowledge {
+            client = client.http2_prior_knowledge();
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            client = client.http2_keep_alive_interval(interval);
+        }
+
+        Fetch::from_client_builder(
+            self.base_url.as_deref().unwrap_or_default(),
+            self.config,
+            client,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{error::BuildError, EndpointSet, Fetch, FetchConfig, FetchError};
+
+    fn build_error(result: Result<Fetch, FetchError>) -> BuildError {
+        match result {
+            Err(FetchError::Build(error)) => error,
+            other => panic!("expected a build error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_settings_are_reported() {
+        assert!(matches!(
+            build_error(Fetch::builder().build()),
+            BuildError::MissingBaseUrl
+        ));
+        assert!(matches!(
+            build_error(Fetch::builder().base_url("ftp://files.local").build()),
+            BuildError::InvalidBaseUrl(_)
+        ));
+        assert!(matches!(
+            build_error(
+                Fetch::builder()
+                    .base_url("http://localhost")
+                    .header("x-bad", "line\nbreak")
+                    .build()
+            ),
+            BuildError::InvalidHeader(name, _) if name == "x-bad"
+        ));
+        assert!(matches!(
+            build_error(
+                Fetch::builder()
+                    .base_url("http://localhost")
+                    .proxy("not a proxy")
+                    .build()
+            ),
+            BuildError::InvalidProxy(..)
+        ));
+        assert!(matches!(
+            build_error(
+                Fetch::builder()
+                    .base_url("http://localhost")
+                    .add_root_certificate_pem(b"not a certificate")
+                    .build()
+            ),
+            BuildError::InvalidCertificate(_)
+        ));
+        assert!(matches!(
+            build_error(
+                Fetch::builder()
+                    .base_url("http://localhost")
+                    .timeout(Duration::from_secs(5))
+                    .deadline(Duration::from_secs(1))
+                    .build()
+            ),
+            BuildError::InvalidTimeout(_)
+        ));
+        assert!(matches!(
+            build_error(
+                Fetch::builder()
+                    .base_url("http://localhost")
+                    .http1_only()
+                    .http2_prior_knowledge()
+                    .build()
+            ),
+            BuildError::ConflictingOptions(..)
+        ));
+        assert!(matches!(
+            build_error(
+                Fetch::builder()
+                    .base_url("http://localhost")
+                    .config(FetchConfig {
+                        verify_digests: true,
+                        ..Default::default()
+                    })
+                    .brotli(true)
+                    .build()
+            ),
+            BuildError::ConflictingOptions("verify_digests", "brotli")
+        ));
+    }
+
+    #[test]
+    fn test_endpoints_replace_the_base_url() {
+        let fetch = Fetch::builder()
+            .endpoints(EndpointSet::new(["http://a.local"]))
+            .build()
+            .unwrap();
+        assert_eq!(
+            "http://a.local/orders",
+            fetch.build_url("/orders", None).unwrap().as_str()
+        );
+    }
+}
@@ -1,11 +1,113 @@
-use crate::{FetchHeaders, fetch_options::ContentType};
+use std::sync::Arc;
+
+use reqwest::header::{HeaderMap, ACCEPT, CONTENT_TYPE};
+
+use crate::{
+    error::FetchResult, fetch_options::ContentType, utils::map_to_reqwest_headers,
+    AdaptiveThrottle, Bulkhead, CircuitBreaker, ContentDigestAlgorithm, EndpointSet, FetchAuth,
+    FetchHeaders, HedgePolicy, HttpCache, Middleware, RateLimiter, RequestSigner, RetryPolicy,
+    USER_AGENT,
+};
 
 #[derive(Default, Debug, Clone)]
 pub struct FetchConfig {
+    /// How long a single attempt may take, from sending the request to reading its body
+    /// (overrideable via FetchOptions)
     pub timeout_ms: Option<u64>,
+    /// How long establishing a connection may take
+    pub connect_timeout_ms: Option<u64>,
+    /// How long to wait for the next bytes of a response before giving up
+    pub read_timeout_ms: Option<u64>,
+    /// How long a whole call may take, including retries, backoff and waiting for rate limits or
+    /// bulkheads (overrideable via FetchOptions)
+    pub deadline_ms: Option<u64>,
     pub headers: Option<FetchHeaders>,
     /// What content-type should these requests accept (overrideable via FetchOptions)
     pub accept: ContentType,
     /// What content-type does do these requests send (overrideable via FetchOptions)
-    pub content_type: ContentType
+    pub content_type: ContentType,
+    /// How requests are authenticated. Clones of this config share cached credentials
+    pub auth: Option<FetchAuth>,
+    /// Sees every request before it is signed and every response, see `Middleware`
+    pub middleware: Vec<Arc<dyn Middleware>>,
+    /// Signs every request after it has been built, e.g. with `SigV4Signer`
+    pub signer: Option<Arc<dyn RequestSigner>>,
+    /// Attaches a `Content-Digest` header to every request body
+    pub content_digest: Option<ContentDigestAlgorithm>,
+    /// Checks response bodies against the `Content-Digest` and `Repr-Digest` headers sent by the
+    /// server, failing with `FetchError::DigestMismatch`. Responses to `HEAD` requests, `204` and
+    /// `304` responses have no body to check. As the digests cover the body as sent, this turns
+    /// off transparent response decompression (see `FetchBuilder::gzip`)
+    pub verify_digests: bool,
+    /// Caches `GET` responses according to their `Cache-Control`, `ETag` and `Last-Modified` headers
+    pub cache: Option<HttpCache>,
+    /// Limits how fast requests are sent. Clones of this config share the budget
+    pub rate_limiter: Option<RateLimiter>,
+    /// Paces requests according to the rate limit headers of earlier responses
+    pub throttle: Option<AdaptiveThrottle>,
+    /// Fails fast with `FetchError::CircuitOpen` while a host keeps failing
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Limits concurrent calls per host or endpoint
+    pub bulkhead: Option<Bulkhead>,
+    /// Sends a second copy of slow idempotent requests
+    pub hedging: Option<HedgePolicy>,
+    /// Retries failed idempotent requests, optionally within a shared `RetryBudget`
+    pub retry: Option<RetryPolicy>,
+    /// Spreads requests over several base URLs instead of the one `Fetch` was created with
+    pub endpoints: Option<EndpointSet>,
+}
+
+/// The part of `FetchConfig` that can be changed while a `Fetch` is in use, see
+/// `Fetch::update_defaults`.
+///
+/// Every call takes a snapshot of the defaults when it starts, so an update only affects calls
+/// started afterwards. The connect and read timeouts are not part of it: they are settings of
+/// the underlying client and stay fixed once `Fetch` is built.
+#[derive(Debug, Clone)]
+pub struct FetchDefaults {
+    /// Sent with every request. The `user-agent`, `content-type` and `accept` entries are always
+    /// set from `USER_AGENT`, `content_type` and `accept`
+    pub headers: FetchHeaders,
+    /// What content-type should requests accept (overrideable via FetchOptions)
+    pub accept: ContentType,
+    /// What content-type do requests send (overrideable via FetchOptions)
+    pub content_type: ContentType,
+    /// How long a single attempt may take (overrideable via FetchOptions). `None` leaves attempts
+    /// without a limit
+    pub timeout_ms: Option<u64>,
+    /// How long a whole call may take, including retries (overrideable via FetchOptions)
+    pub deadline_ms: Option<u64>,
+    /// `headers`, parsed once instead of for every request
+    header_map: HeaderMap,
+}
+
+impl FetchDefaults {
+    pub(crate) fn from_config(config: &FetchConfig) -> FetchResult<Self> {
+        let mut defaults = Self {
+            headers: config.headers.clone().unwrap_or_default(),
+            accept: config.accept.clone(),
+            content_type: config.content_type.clone(),
+            timeout_ms: config.timeout_ms,
+            deadline_ms: config.deadline_ms,
+            header_map: HeaderMap::new(),
+        };
+        defaults.finish()?;
+        Ok(defaults)
+    }
+
+    /// Fills in the standard headers and parses all of them, failing on invalid ones
+    pub(crate) fn finish(&mut self) -> FetchResult<()> {
+        self.headers
+            .insert("user-agent".to_string(), USER_AGENT.to_string());
+        self.headers
+            .insert(CONTENT_TYPE.to_string(), self.content_type.to_string());
+        self.headers
+            .insert(ACCEPT.to_string(), self.accept.to_string());
+        self.header_map = map_to_reqwest_headers(&self.headers)?;
+        Ok(())
+    }
+
+    pub(crate) fn header_map(&self) -> &HeaderMap {
+        &self.header_map
+    }
 }
@@ -1,4 +1,4 @@
-use crate::FetchHeaders;
+use crate::{CancellationToken, FetchHeaders};
 use std::str::FromStr;
 use std::{collections::HashMap, fmt::Display};
 
@@ -9,6 +9,15 @@ pub struct FetchOptions {
     pub content_type: Option<ContentType>,
     pub params: Option<HashMap<String, String>>,
     pub deserialize_body: bool,
+    /// Sends an `Idempotency-Key` header, so the server can safely deduplicate retried requests
+    pub idempotency_key: Option<IdempotencyKey>,
+    /// Overrides `FetchConfig::timeout_ms` for this call
+    pub timeout_ms: Option<u64>,
+    /// Overrides `FetchConfig::deadline_ms` for this call
+    pub deadline_ms: Option<u64>,
+    /// Aborts the call with `FetchError::Cancelled` once cancelled, whether it is waiting to be
+    /// sent, waiting for a response, downloading the body or backing off before a retry
+    pub cancellation_token: Option<CancellationToken>,
 }
 
 impl Default for FetchOptions {
@@ -19,14 +28,47 @@ impl Default for FetchOptions {
             accept: Default::default(),
             content_type: Default::default(),
             deserialize_body: true,
+            idempotency_key: Default::default(),
+            timeout_ms: Default::default(),
+            deadline_ms: Default::default(),
+            cancellation_token: Default::default(),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// The `Idempotency-Key` to send with a request
+///
+/// # Example
+/// ```rust
+/// use rust_fetch::{FetchOptions, IdempotencyKey};
+///
+/// let options = FetchOptions {
+///     idempotency_key: Some(IdempotencyKey::Auto),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyKey {
+    /// Generate a random UUID v4 for the call
+    Auto,
+    /// Use the given key, e.g. to replay an operation that failed earlier
+    Key(String),
+}
+
+impl IdempotencyKey {
+    pub(crate) fn resolve(&self) -> String {
+        match self {
+            IdempotencyKey::Auto => uuid::Uuid::new_v4().to_string(),
+            IdempotencyKey::Key(key) => key.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 /// Suppling Fetch with a ContentType will set the `content-type` header as well as change how the data is serialized to the server
 pub enum ContentType {
     /// Serialize as `application/json`
+    #[default]
     Json,
     /// Serialize as `text/xml`
     TextXml,
@@ -40,13 +82,13 @@ impl FromStr for ContentType {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        return match s {
+        match s {
             "application/json" => Ok(Self::Json),
             "text/xml" => Ok(Self::TextXml),
             "application/xml" => Ok(Self::ApplicationXml),
             "application/x-www-form-urlencoded" => Ok(Self::UrlEncoded),
             _ => Err(()),
-        };
+        }
     }
 }
 
@@ -67,15 +109,17 @@ impl Display for ContentType {
     }
 }
 
-impl Default for ContentType {
-    fn default() -> Self {
-        Self::Json
-    }
-}
-
 #[cfg(test)]
 mod tests {
-    use super::ContentType;
+    use super::{ContentType, IdempotencyKey};
+
+    #[test]
+    fn test_idempotency_key_resolve() {
+        let auto = IdempotencyKey::Auto.resolve();
+        assert_eq!(36, auto.len());
+        assert_ne!(auto, IdempotencyKey::Auto.resolve());
+        assert_eq!("abc", IdempotencyKey::Key("abc".to_string()).resolve());
+    }
 
     #[test]
     fn test_content_type_json_to_string() {
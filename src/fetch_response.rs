@@ -1,9 +1,9 @@
 use bytes::Bytes;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 
 use reqwest::StatusCode;
 
-use crate::FetchHeaders;
+use crate::{CacheStatus, FetchHeaders, ResourceVersion};
 
 #[derive(Debug)]
 pub struct FetchResponse<T> {
@@ -12,4 +12,17 @@ pub struct FetchResponse<T> {
     pub status: StatusCode,
     pub response_headers: FetchHeaders,
     pub remote_address: Option<SocketAddr>,
+    /// Whether the response was served from the HTTP cache
+    pub cache_status: CacheStatus,
+    /// The `Idempotency-Key` sent with the request, if any
+    pub idempotency_key: Option<String>,
+    /// How long the call waited for a slot in the configured `Bulkhead`
+    pub queue_wait: Option<Duration>,
+}
+
+impl<T> FetchResponse<T> {
+    /// The `ETag` and `Last-Modified` validators of the response
+    pub fn version(&self) -> ResourceVersion {
+        ResourceVersion::from_headers(&self.response_headers)
+    }
 }
@@ -0,0 +1,138 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::Method;
+
+/// Sends a second copy of a slow GET or HEAD request and uses whichever usable response arrives
+/// first, cancelling the other one. A `5xx` or `429` response does not win the race while the
+/// other attempt is still running.
+///
+/// A hedge is only sent when the first attempt has not answered within `delay`, and only while
+/// the budget allows it: every request adds `budget` hedge tokens (up to `max_tokens`), and every
+/// hedge costs one. With the default budget of `0.1`, hedges add at most ~10% to the load. A
+/// hedge also needs a free slot in the configured `RateLimiter` and `Bulkhead`; it is skipped
+/// rather than queued when there is none.
+///
+/// Clones share their budget.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rust_fetch::{Fetch, FetchConfig, HedgePolicy};
+///
+/// let client = Fetch::new(
+///     "https://replicated-reads.local",
+///     Some(FetchConfig {
+///         hedging: Some(HedgePolicy::new(Duration::from_millis(120)).with_budget(0.05)),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct HedgePolicy {
+    pub(crate) delay: Duration,
+    methods: Vec<Method>,
+    budget: f64,
+    max_tokens: f64,
+    tokens: Arc<Mutex<f64>>,
+}
+
+impl HedgePolicy {
+    /// Hedges requests that have not answered within `delay`, e.g. the p95 latency
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            methods: vec![Method::GET, Method::HEAD],
+            budget: 0.1,
+            max_tokens: 10.0,
+            tokens: Arc::new(Mutex::new(10.0)),
+        }
+    }
+
+    /// The number of hedges allowed per request sent, between 0 and 1
+    pub fn with_budget(mut self, budget: f64) -> Self {
+        self.budget = budget.clamp(0.0, 1.0);
+        self
+    }
+
+    /// How many unused hedges can be saved up for a burst of slow responses
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens as f64;
+        *self.tokens.lock().unwrap() = self.max_tokens;
+        self
+    }
+
+    /// The methods that may be sent twice, `GET` and `HEAD` by default. Only opt in methods
+    /// whose requests are safe to apply twice on this API, e.g. idempotent `PUT`s
+    pub fn with_methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.methods = methods.into_iter().collect();
+        self
+    }
+
+    pub(crate) fn applies_to(&self, method: &Method) -> bool {
+        self.methods.contains(method)
+    }
+
+    /// Records a request towards the budget
+    pub(crate) fn deposit(&self) {
+        let mut tokens = self.tokens.lock().unwrap();
+        *tokens = (*tokens + self.budget).min(self.max_tokens);
+    }
+
+    /// Takes a token for a hedge, returning false if the budget is exhausted
+    pub(crate) fn try_spend(&self) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::Method;
+
+    use super::HedgePolicy;
+
+    #[test]
+    fn test_budget_limits_hedges() {
+        let policy = HedgePolicy::new(Duration::from_millis(10))
+            .with_budget(0.5)
+            .with_max_tokens(1);
+
+        assert!(policy.try_spend());
+        assert!(!policy.try_spend());
+        policy.deposit();
+        assert!(!policy.try_spend());
+        policy.deposit();
+        assert!(policy.try_spend());
+    }
+
+    #[test]
+    fn test_only_safe_methods_are_hedged_by_default() {
+        let policy = HedgePolicy::new(Duration::from_millis(10));
+
+        assert!(policy.applies_to(&Method::GET));
+        assert!(policy.applies_to(&Method::HEAD));
+        assert!(!policy.applies_to(&Method::PUT));
+        assert!(!policy.applies_to(&Method::DELETE));
+        assert!(!policy.applies_to(&Method::POST));
+    }
+
+    #[test]
+    fn test_methods_can_be_opted_in() {
+        let policy =
+            HedgePolicy::new(Duration::from_millis(10)).with_methods([Method::GET, Method::PUT]);
+
+        assert!(policy.applies_to(&Method::PUT));
+        assert!(!policy.applies_to(&Method::HEAD));
+    }
+}
@@ -1,50 +1,148 @@
+mod auth;
+mod bulkhead;
+mod cache;
+mod circuit_breaker;
+mod content_digest;
+mod digest_auth;
+mod discovery;
+mod disk_cache;
+mod endpoints;
 mod error;
+mod fetch_builder;
 mod network_error;
 mod fetch_config;
 mod fetch_options;
 mod fetch_response;
+mod hedging;
+mod message_signature;
+mod middleware;
+mod oauth2;
+mod rate_limit;
+mod request_builder;
+mod retry;
+mod signing;
+mod sigv4;
+mod throttle;
 mod utils;
+mod versioned;
 
-use anyhow::anyhow;
+pub use auth::FetchAuth;
+pub use bulkhead::{Bulkhead, BulkheadKey};
 use bytes::Bytes;
-pub use error::{DeserializationError, FetchError, FetchResult, SerializationError};
+pub use cache::{CacheStatus, CacheStore, CachedResponse, HttpCache, MemoryCacheStore};
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
+pub use content_digest::ContentDigestAlgorithm;
+pub use digest_auth::{DigestAlgorithm, DigestAuth};
+pub use discovery::{DiscoverySource, FileDiscovery};
+pub use disk_cache::DiskCacheStore;
+pub use endpoints::{EndpointSet, SelectionStrategy};
+pub use error::{
+    BuildError, DeserializationError, FetchError, FetchResult, OAuth2Error, SerializationError,
+    TimeoutPhase,
+};
 pub use network_error::NetworkError;
-pub use fetch_config::FetchConfig;
-pub use fetch_options::{ContentType, FetchOptions};
+pub use fetch_builder::FetchBuilder;
+pub use fetch_config::{FetchConfig, FetchDefaults};
+pub use fetch_options::{ContentType, FetchOptions, IdempotencyKey};
 pub use fetch_response::FetchResponse;
+pub use hedging::HedgePolicy;
+pub use message_signature::{
+    Ed25519Key, HmacSha256Key, MessageSigner, MessageVerifier, SignatureAlgorithm,
+    SignatureComponent, SignatureKey,
+};
+pub use middleware::Middleware;
+pub use oauth2::{
+    AccessToken, ClientAuthMethod, ClientCredentials, DeviceAuthorization, DeviceCode,
+    MemoryTokenStore, RefreshTokenGrant, TokenStore,
+};
+pub use rate_limit::{RateLimitKey, RateLimitMode, RateLimiter};
+pub use request_builder::FetchRequestBuilder;
 pub use reqwest;
+pub use retry::{RetryBudget, RetryPolicy};
 pub use reqwest::StatusCode;
-use reqwest::{header::HeaderMap, Client, ClientBuilder, RequestBuilder, Response, Url};
+pub use signing::RequestSigner;
+pub use sigv4::{AwsCredentials, SigV4Signer};
+pub use throttle::{AdaptiveThrottle, Quota};
+pub use tokio_util::sync::CancellationToken;
+pub use versioned::{ResourceVersion, Versioned};
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Client, ClientBuilder, Method, Request, RequestBuilder, Response, Url,
+};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::str::FromStr;
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::{Duration, Instant, SystemTime},
+};
+use auth::Authorization;
 use utils::{map_to_reqwest_headers, reqwest_headers_to_map};
 
 pub type FetchHeaders = HashMap<String, String>;
-pub const USER_AGENT: &'static str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
 
-#[derive(Debug)]
+/// Attached to every response, so a timeout while reading its body can be told apart from a read
+/// timeout
+#[derive(Debug, Clone, Copy)]
+struct Attempt {
+    started: Instant,
+    timeout: Option<Duration>,
+    /// Responses to `HEAD` requests carry the digests of a body they do not have
+    head: bool,
+}
+
+/// The outcome of sending a request through the cache
+enum CacheFill {
+    Revalidated(CachedResponse),
+    Fetched {
+        status: StatusCode,
+        headers: HeaderMap,
+        raw_body: Option<Bytes>,
+        remote_address: Option<SocketAddr>,
+    },
+}
+
+/// Clones are cheap and share the connection pool, as well as the state of caches, rate limiters,
+/// circuit breakers and the like
+#[derive(Debug, Clone)]
 pub struct Fetch {
     client: Client,
     pub config: Option<FetchConfig>,
     base_url: String,
+    /// Prepended to every endpoint, see `with_base_path`
+    base_path: String,
+    /// Sent with every request on top of the default headers, see `with_headers`
+    scoped_headers: FetchHeaders,
+    /// Shared by all clones, see `update_defaults`
+    defaults: Arc<RwLock<Arc<FetchDefaults>>>,
+    /// Held by `update_defaults` while the update runs, so concurrent updates apply one by one
+    /// without blocking readers
+    updating_defaults: Arc<Mutex<()>>,
 }
 
 impl Default for Fetch {
     fn default() -> Self {
-        let mut headers: FetchHeaders = HashMap::new();
-        Self::insert_default_headers(&mut headers, Default::default());
+        let defaults = FetchDefaults::from_config(&Default::default()).unwrap();
 
         Self {
             client: ClientBuilder::default()
-                .default_headers(map_to_reqwest_headers(&headers).unwrap())
+                .user_agent(USER_AGENT)
                 .build()
                 .unwrap(),
             config: Some(FetchConfig {
-                headers: Some(headers),
+                headers: Some(defaults.headers.clone()),
                 ..Default::default()
             }),
             base_url: Default::default(),
+            base_path: Default::default(),
+            scoped_headers: Default::default(),
+            defaults: Arc::new(RwLock::new(Arc::new(defaults))),
+            updating_defaults: Default::default(),
         }
     }
 }
@@ -60,81 +158,211 @@ impl Fetch {
     ///
     /// ```
     pub fn new(base_url: &str, options: Option<FetchConfig>) -> FetchResult<Self> {
-        let mut options = options.unwrap_or_default();
-        let mut headers = options
-            .headers
-            .as_ref()
-            .map(|r| r.clone())
-            .unwrap_or_default();
+        Self::from_client_builder(
+            base_url,
+            options.unwrap_or_default(),
+            ClientBuilder::default(),
+        )
+    }
+
+    /// Starts building a Fetch with connection settings beyond those of `FetchConfig`
+    ///
+    /// # Example
+    /// ```rust
+    /// use rust_fetch::Fetch;
+    /// let client = Fetch::builder()
+    ///     .base_url("http://localhost")
+    ///     .proxy("http://proxy.local:3128")
+    ///     .build();
+    /// assert_ne!(true, client.is_err());
+    ///
+    /// ```
+    pub fn builder() -> FetchBuilder {
+        FetchBuilder::new()
+    }
 
-        Self::insert_default_headers(&mut headers, Some(&options));
-        options.headers = Some(headers);
+    pub(crate) fn from_client_builder(
+        base_url: &str,
+        options: FetchConfig,
+        client: ClientBuilder,
+    ) -> FetchResult<Self> {
+        let mut options = options;
+        let defaults = FetchDefaults::from_config(&options)?;
+        options.headers = Some(defaults.headers.clone());
 
-        let default_headers: HeaderMap;
-        let mut client = ClientBuilder::default();
-        if let Some(headers) = &options.headers {
-            default_headers = map_to_reqwest_headers(&headers)?;
-            client = client.default_headers(default_headers);
+        // Default headers and the timeout are added per request, so they can change without a new
+        // client
+        let mut client = client.user_agent(USER_AGENT);
+        // Digests cover the body as sent, so a client checking them must not decompress it
+        if options.verify_digests {
+            client = client.gzip(false).brotli(false).deflate(false);
         }
-        if let Some(timeout) = &options.timeout_ms {
-            client = client.timeout(Duration::from_millis(timeout.to_owned()))
+        if let Some(timeout) = &options.connect_timeout_ms {
+            client = client.connect_timeout(Duration::from_millis(timeout.to_owned()))
+        }
+        if let Some(timeout) = &options.read_timeout_ms {
+            client = client.read_timeout(Duration::from_millis(timeout.to_owned()))
         }
 
         Ok(Self {
             base_url: base_url.to_string(),
+            base_path: Default::default(),
+            scoped_headers: Default::default(),
             config: Some(options),
             client: client
                 .build()
-                .map_err(|e| FetchError::Unknown(anyhow!(e)))?,
-            ..Default::default()
+                .map_err(|e| FetchError::Build(BuildError::Client(e)))?,
+            defaults: Arc::new(RwLock::new(Arc::new(defaults))),
+            updating_defaults: Default::default(),
         })
     }
 
-    fn insert_default_headers(headers: &mut FetchHeaders, config: Option<&FetchConfig>) {
-        headers.insert("user-agent".to_string(), USER_AGENT.to_string());
-        if let Some(config) = config {
-            headers.insert(
-                reqwest::header::CONTENT_TYPE.to_string(),
-                config.content_type.clone().to_string(),
-            );
-            headers.insert(
-                reqwest::header::ACCEPT.to_string(),
-                config.accept.clone().to_string(),
-            );
-        }
+    /// Creates a new instance of Fetch that spreads its requests over a set of base urls
+    ///
+    /// # Example
+    /// ```rust
+    /// use rust_fetch::{EndpointSet, Fetch, SelectionStrategy};
+    ///
+    /// let endpoints = EndpointSet::new(["http://replica-1.local", "http://replica-2.local"])
+    ///     .with_strategy(SelectionStrategy::PrimaryFailover);
+    /// let client = Fetch::from_endpoints(endpoints, None);
+    /// assert_ne!(true, client.is_err());
+    ///
+    /// ```
+    pub fn from_endpoints(
+        endpoints: EndpointSet,
+        options: Option<FetchConfig>,
+    ) -> FetchResult<Self> {
+        let options = FetchConfig {
+            endpoints: Some(endpoints),
+            ..options.unwrap_or_default()
+        };
+        Self::new("", Some(options))
     }
 
-    /// Sets the default headers for this instance of Fetch.
+    /// Sets the default headers for this instance of Fetch, its clones and derived handles.
     ///
     /// # Example
     /// ```rust
     /// use rust_fetch::{Fetch, map_string};
     ///
-    /// let mut client = Fetch::new("http://localhost", None).unwrap();
+    /// let client = Fetch::new("http://localhost", None).unwrap();
     /// let set_header_result = client.set_default_headers(Some(map_string!{ header1 : "header 1 value" }));
     /// assert_ne!(true, set_header_result.is_err());
     ///
     /// ```
-    pub fn set_default_headers(&mut self, headers: Option<FetchHeaders>) -> FetchResult<()> {
-        let mut headers = headers.unwrap_or_default();
+    pub fn set_default_headers(&self, headers: Option<FetchHeaders>) -> FetchResult<()> {
+        self.update_defaults(|defaults| defaults.headers = headers.unwrap_or_default())
+    }
 
-        Self::insert_default_headers(&mut headers, self.config.as_ref());
+    /// The defaults calls started now are sent with
+    pub fn defaults(&self) -> Arc<FetchDefaults> {
+        self.defaults
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
 
-        let opts: FetchConfig = FetchConfig {
-            headers: Some(headers),
-            ..self.config.clone().unwrap_or(Default::default())
-        };
+    /// Changes the default headers, content types or timeouts of this instance, its clones and
+    /// derived handles, without rebuilding the client or its connection pool.
+    ///
+    /// Calls already in flight keep the defaults they started with. Cached responses are kept
+    /// apart by the headers they were requested with, so a swapped tenant or token header never
+    /// serves the previous one's responses. If the updated headers are
+    /// invalid or `update` panics, the defaults are left unchanged. `FetchConfig` keeps the initial values, and
+    /// connect and read timeouts are fixed once the client is built.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rust_fetch::{ContentType, Fetch};
+    ///
+    /// let client = Fetch::new("http://localhost", None).unwrap();
+    /// let result = client.update_defaults(|defaults| {
+    ///     defaults.headers.insert("x-tenant".to_string(), "acme".to_string());
+    ///     defaults.accept = ContentType::TextXml;
+    ///     defaults.timeout_ms = Some(2_000);
+    /// });
+    /// assert_ne!(true, result.is_err());
+    /// assert_eq!(Some(2_000), client.defaults().timeout_ms);
+    ///
+    /// ```
+    pub fn update_defaults(&self, update: impl FnOnce(&mut FetchDefaults)) -> FetchResult<()> {
+        // `update` runs without the defaults locked, so it may read them, and a panic in it
+        // leaves them untouched
+        let _updating = self
+            .updating_defaults
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut updated = FetchDefaults::clone(&self.defaults());
+        update(&mut updated);
+        updated.finish()?;
+        *self
+            .defaults
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Arc::new(updated);
+        Ok(())
+    }
 
-        let new_fetch = Self::new(&self.base_url, Some(opts))?;
-        self.client = new_fetch.client;
-        self.config = new_fetch.config;
+    /// Derives a handle that sends `headers` with every request, on top of the default headers.
+    /// The handle shares the connection pool and all other state with this one, but caches its
+    /// responses apart from handles with other headers
+    ///
+    /// # Example
+    /// ```rust
+    /// use rust_fetch::{Fetch, map_string};
+    ///
+    /// let client = Fetch::new("http://localhost", None).unwrap();
+    /// let acme = client.with_headers(map_string!{ "x-tenant" => "acme" });
+    /// let globex = client.with_headers(map_string!{ "x-tenant" => "globex" });
+    ///
+    /// ```
+    pub fn with_headers(&self, headers: FetchHeaders) -> Self {
+        let mut scoped = self.clone();
+        scoped.scoped_headers.extend(headers);
+        scoped
+    }
 
-        Ok(())
+    /// Derives a handle whose endpoints are relative to `base_path`, e.g. `/v2`. Calling it on a
+    /// derived handle nests the paths
+    ///
+    /// # Example
+    /// ```rust
+    /// use rust_fetch::Fetch;
+    ///
+    /// let client = Fetch::new("http://localhost/api", None).unwrap();
+    /// let v2 = client.with_base_path("/v2");
+    /// assert_eq!(
+    ///     "http://localhost/api/v2/orders",
+    ///     v2.build_url("/orders", None).unwrap().as_str()
+    /// );
+    ///
+    /// ```
+    pub fn with_base_path(&self, base_path: &str) -> Self {
+        let mut scoped = self.clone();
+        let base_path = base_path.trim_matches('/');
+        if !base_path.is_empty() {
+            scoped.base_path = format!("{}/{base_path}", self.base_path);
+        }
+        scoped
+    }
+
+    /// Derives a handle that authenticates its requests with `auth` instead of the configured
+    /// authentication. Responses are cached apart from those of other users
+    pub fn with_auth(&self, auth: FetchAuth) -> Self {
+        let mut scoped = self.clone();
+        scoped.config.get_or_insert_with(Default::default).auth = Some(auth);
+        scoped
     }
 
     pub fn build_url(&self, endpoint: &str, options: Option<&FetchOptions>) -> FetchResult<Url> {
         let mut built_string = String::new();
-        built_string += &self.base_url;
+        match self.config.as_ref().and_then(|c| c.endpoints.as_ref()) {
+            Some(endpoints) => built_string += &endpoints.select()?,
+            None => built_string += &self.base_url,
+        }
+        if !self.base_path.is_empty() {
+            built_string = built_string.trim_end_matches('/').to_string() + &self.base_path;
+        }
 
         if built_string.chars().nth(built_string.chars().count() - 1) != Some('/')
             && endpoint.chars().nth(0) != Some('/')
@@ -166,7 +394,7 @@ impl Fetch {
         Ok(url)
     }
 
-    fn make_body<U>(
+    pub(crate) fn make_body<U>(
         &self,
         data: U,
         options: Option<&FetchOptions>,
@@ -174,21 +402,10 @@ impl Fetch {
     where
         U: Serialize,
     {
-        let mut content_type: ContentType = Default::default();
-
-        if let Some(opts) = options {
-            if let Some(ref c_type) = opts.content_type {
-                content_type = c_type.clone();
-            } else {
-                if let Some(ref config) = self.config {
-                    content_type = config.content_type.clone();
-                }
-            }
-        } else {
-            if let Some(ref config) = self.config {
-                content_type = config.content_type.clone();
-            }
-        }
+        let content_type = match options.and_then(|opts| opts.content_type.as_ref()) {
+            Some(c_type) => c_type.clone(),
+            None => self.defaults().content_type.clone(),
+        };
 
         let data_to_return = match content_type {
             ContentType::Json => serde_json::to_vec(&data)
@@ -201,36 +418,56 @@ impl Fetch {
                 .into_bytes(),
         };
 
-        return Ok((data_to_return, content_type));
+        Ok((data_to_return, content_type))
     }
 
-    fn build_request<U>(
+    /// The default, scoped and per-call headers of a call. Later headers replace earlier ones with
+    /// the same name
+    fn call_headers(
         &self,
-        data: Option<U>,
+        defaults: &FetchDefaults,
         options: Option<&FetchOptions>,
-        original_builder: RequestBuilder,
-    ) -> FetchResult<RequestBuilder>
-    where
-        U: Serialize,
-    {
-        let mut builder = original_builder;
+    ) -> FetchResult<HeaderMap> {
+        let mut headers = defaults.header_map().clone();
+        if !self.scoped_headers.is_empty() {
+            headers.extend(map_to_reqwest_headers(&self.scoped_headers)?);
+        }
         if let Some(options) = options {
-            if let Some(headers) = &options.headers {
-                builder = builder.headers(map_to_reqwest_headers(headers)?);
+            if let Some(option_headers) = &options.headers {
+                headers.extend(map_to_reqwest_headers(option_headers)?);
             }
         };
-        if let Some(body) = data {
-            let (body, content_type) = self.make_body(body, options)?;
+        Ok(headers)
+    }
+
+    fn build_request(
+        &self,
+        mut headers: HeaderMap,
+        body: Option<(Vec<u8>, ContentType)>,
+        options: Option<&FetchOptions>,
+        original_builder: RequestBuilder,
+    ) -> FetchResult<RequestBuilder> {
+        let mut builder = original_builder;
+        if let Some((body, content_type)) = body {
+            if let Some(algorithm) = self.config.as_ref().and_then(|c| c.content_digest) {
+                builder = builder.header("content-digest", algorithm.header_value(&body));
+            }
             builder = builder.body(body);
-            builder = builder.header(reqwest::header::CONTENT_TYPE, format!("{content_type}"));
+            headers.insert(
+                reqwest::header::CONTENT_TYPE,
+                HeaderValue::from_str(&content_type.to_string()).unwrap(),
+            );
         }
         if let Some(opts) = options {
             if let Some(ref accept) = opts.accept {
-                builder = builder.header(reqwest::header::ACCEPT.to_string(), accept.to_string());
+                headers.insert(
+                    reqwest::header::ACCEPT,
+                    HeaderValue::from_str(&accept.to_string()).unwrap(),
+                );
             }
         }
 
-        return Ok(builder);
+        Ok(builder.headers(headers))
     }
 
     fn deserialize_response<T>(
@@ -241,7 +478,7 @@ impl Fetch {
     where
         T: for<'de> Deserialize<'de>,
     {
-        return match content_type {
+        match content_type {
             ContentType::Json => Ok(serde_json::from_slice::<T>(raw_body)
                 .map_err(|e| FetchError::DeserializationError(DeserializationError::Json(e)))?),
             ContentType::TextXml | ContentType::ApplicationXml => {
@@ -258,10 +495,21 @@ impl Fetch {
                     FetchError::DeserializationError(DeserializationError::UrlEncoded(e))
                 })?)
             }
-        };
+        }
     }
 
     async fn check_response_and_return_err(&self, response: Response) -> FetchResult<Response> {
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            let etag = response
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|etag| etag.to_str().ok())
+                .map(|etag| etag.to_string());
+            return Err(FetchError::PreconditionFailed {
+                etag,
+                error: NetworkError::new(response).await,
+            });
+        }
         if response.status().is_client_error() || response.status().is_server_error() {
             return Err(FetchError::NetworkError(NetworkError::new(response).await));
         }
@@ -277,8 +525,80 @@ impl Fetch {
         T: for<'de> Deserialize<'de>,
     {
         let response = self.check_response_and_return_err(response).await?;
-        let remote_content_type = response
-            .headers()
+        let headers = response.headers().clone();
+        let remote_address = response.remote_addr();
+        let status = response.status();
+        let raw_body = self.read_body(response).await?;
+
+        self.parts_to_fetch_response(status, &headers, raw_body, remote_address, deserialize_body)
+    }
+
+    /// Reads the body of `response` and, if `FetchConfig::verify_digests` is set, checks it against
+    /// the digests sent along. Besides a mismatching digest only timeouts are reported, a body
+    /// that cannot be read for any other reason is treated as missing.
+    ///
+    /// Every body is checked here, before it is cached, so cached bodies need no further checks
+    async fn read_body(&self, response: Response) -> FetchResult<Option<Bytes>> {
+        let attempt = response.extensions().get::<Attempt>().copied();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let verify_digests = self.config.as_ref().is_some_and(|c| c.verify_digests)
+            && !attempt.is_some_and(|attempt| attempt.head)
+            && !matches!(status, StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED);
+        match response.bytes().await {
+            Ok(body) => {
+                if verify_digests {
+                    content_digest::verify(status, &headers, &body)?;
+                }
+                Ok(Some(body))
+            }
+            Err(err) => match attempt.and_then(|attempt| self.timeout_error(&err, attempt)) {
+                Some(timeout) => Err(timeout),
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Turns a timeout of `attempt` into a `FetchError::Timeout` naming the phase that expired
+    fn timeout_error(&self, err: &reqwest::Error, attempt: Attempt) -> Option<FetchError> {
+        if !err.is_timeout() {
+            return None;
+        }
+        let config = self.config.as_ref();
+        let connect_timeout = config.and_then(|c| c.connect_timeout_ms);
+        let read_timeout = config.and_then(|c| c.read_timeout_ms);
+        let (phase, after) = match (attempt.timeout, read_timeout) {
+            _ if err.is_connect() => (
+                TimeoutPhase::Connect,
+                connect_timeout.map(Duration::from_millis),
+            ),
+            (Some(timeout), Some(_)) if attempt.started.elapsed() >= timeout => {
+                (TimeoutPhase::Request, Some(timeout))
+            }
+            (_, Some(read_timeout)) => (
+                TimeoutPhase::Read,
+                Some(Duration::from_millis(read_timeout)),
+            ),
+            (timeout, None) => (TimeoutPhase::Request, timeout),
+        };
+        Some(FetchError::Timeout {
+            phase,
+            after: after.unwrap_or_else(|| attempt.started.elapsed()),
+        })
+    }
+
+    fn parts_to_fetch_response<T>(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        raw_body: Option<Bytes>,
+        remote_address: Option<SocketAddr>,
+        deserialize_body: bool,
+    ) -> FetchResult<FetchResponse<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let remote_content_type = headers
             .get(reqwest::header::CONTENT_TYPE)
             .map(|c_type| {
                 c_type
@@ -289,11 +609,6 @@ impl Fetch {
             })
             .map(|string| ContentType::from_str(&string).ok().unwrap_or_default());
 
-        let headers = response.headers().clone();
-        let remote_address = response.remote_addr();
-        let status = response.status();
-
-        let raw_body = response.bytes().await.ok();
         let mut body: Option<T> = None;
 
         if let Some(raw_body) = &raw_body {
@@ -306,13 +621,656 @@ impl Fetch {
             }
         }
 
-        return Ok(FetchResponse {
+        Ok(FetchResponse {
             body,
             raw_body,
             status,
-            response_headers: reqwest_headers_to_map(&headers)?,
+            response_headers: reqwest_headers_to_map(headers)?,
             remote_address,
+            cache_status: CacheStatus::Network,
+            idempotency_key: None,
+            queue_wait: None,
+        })
+    }
+
+    fn cached_to_fetch_response<T>(
+        &self,
+        cached: CachedResponse,
+        cache_status: CacheStatus,
+        deserialize_body: bool,
+    ) -> FetchResult<FetchResponse<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut response = self.parts_to_fetch_response(
+            cached.status_code(),
+            &cached.header_map(),
+            Some(Bytes::from(cached.body)),
+            None,
+            deserialize_body,
+        )?;
+        response.cache_status = cache_status;
+        Ok(response)
+    }
+
+    async fn execute<T, U>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        data: Option<U>,
+        options: Option<FetchOptions>,
+    ) -> FetchResult<FetchResponse<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+        U: Serialize,
+    {
+        let body = match data {
+            Some(data) => Some(self.make_body(data, options.as_ref())?),
+            None => None,
+        };
+        self.execute_body(method, endpoint, body, options).await
+    }
+
+    /// Sends a request with an already serialized body through the whole pipeline
+    pub(crate) async fn execute_body<T>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<(Vec<u8>, ContentType)>,
+        options: Option<FetchOptions>,
+    ) -> FetchResult<FetchResponse<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let options = options.unwrap_or_default();
+        let defaults = self.defaults();
+        let headers = self.call_headers(&defaults, Some(&options))?;
+        let cache_partition = self.cache_partition(&headers);
+        let mut request = self
+            .build_request(
+                headers,
+                body,
+                Some(&options),
+                self.client
+                    .request(method, self.build_url(endpoint, Some(&options))?),
+            )?
+            .build()
+            .map_err(|e| FetchError::UnableToSendRequest { err: e })?;
+        if let Some(timeout) = options.timeout_ms.or(defaults.timeout_ms) {
+            *request.timeout_mut() = Some(Duration::from_millis(timeout));
+        }
+        let deadline = options
+            .deadline_ms
+            .or(defaults.deadline_ms)
+            .map(Duration::from_millis);
+
+        // The key is generated once here, so every attempt of this call carries the same one
+        let idempotency_key = options.idempotency_key.as_ref().map(|key| key.resolve());
+        if let Some(idempotency_key) = &idempotency_key {
+            let value = HeaderValue::from_str(idempotency_key).map_err(|e| {
+                FetchError::HeaderParseError(IDEMPOTENCY_KEY.to_string(), e.to_string())
+            })?;
+            request.headers_mut().insert(IDEMPOTENCY_KEY, value);
+        }
+
+        let call = self.send_through_bulkhead(
+            request,
+            cache_partition.as_deref(),
+            options.deserialize_body,
+        );
+        let call = Self::until_cancelled(options.cancellation_token.as_ref(), call);
+        let result = Self::within_deadline(deadline, call).await;
+        let Some(idempotency_key) = idempotency_key else {
+            return result;
+        };
+        match result {
+            Ok(mut response) => {
+                response.idempotency_key = Some(idempotency_key);
+                Ok(response)
+            }
+            Err(err) => Err(FetchError::Idempotent {
+                key: idempotency_key,
+                source: Box::new(err),
+            }),
+        }
+    }
+
+    /// Runs `call`, failing with `FetchError::Cancelled` as soon as `token` is cancelled
+    async fn until_cancelled<T>(
+        token: Option<&CancellationToken>,
+        call: impl Future<Output = FetchResult<T>>,
+    ) -> FetchResult<T> {
+        let Some(token) = token else {
+            return call.await;
+        };
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(FetchError::Cancelled),
+            result = call => result,
+        }
+    }
+
+    /// Runs `call`, failing with a `Deadline` timeout if it does not finish within `deadline`
+    async fn within_deadline<T>(
+        deadline: Option<Duration>,
+        call: impl Future<Output = FetchResult<T>>,
+    ) -> FetchResult<T> {
+        let Some(deadline) = deadline else {
+            return call.await;
+        };
+        tokio::time::timeout(deadline, call)
+            .await
+            .unwrap_or(Err(FetchError::Timeout {
+                phase: TimeoutPhase::Deadline,
+                after: deadline,
+            }))
+    }
+
+    /// Holds a slot of the configured bulkhead for the whole call, including reading the body
+    async fn send_through_bulkhead<T>(
+        &self,
+        request: Request,
+        cache_partition: Option<&str>,
+        deserialize_body: bool,
+    ) -> FetchResult<FetchResponse<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let Some(bulkhead) = self.config.as_ref().and_then(|c| c.bulkhead.as_ref()) else {
+            return self.send(request, cache_partition, deserialize_body).await;
+        };
+        let Some((_permit, queue_wait)) = bulkhead.acquire(&request).await? else {
+            return self.send(request, cache_partition, deserialize_body).await;
+        };
+        let mut response = self
+            .send(request, cache_partition, deserialize_body)
+            .await?;
+        response.queue_wait = Some(queue_wait);
+        Ok(response)
+    }
+
+    async fn send<T>(
+        &self,
+        request: Request,
+        cache_partition: Option<&str>,
+        deserialize_body: bool,
+    ) -> FetchResult<FetchResponse<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        if let Some(cache) = self.config.as_ref().and_then(|c| c.cache.as_ref()) {
+            if HttpCache::handles(&request) {
+                let key = self.cache_key(cache_partition, request.method(), request.url());
+                return self
+                    .execute_cached(cache, key, request, deserialize_body)
+                    .await;
+            }
+            if !request.method().is_safe() {
+                let key = self.cache_key(cache_partition, &Method::GET, request.url());
+                let response = self.send_request(request).await?;
+                if response.status().is_success() {
+                    cache.invalidate(&key).await;
+                }
+                return self
+                    .response_to_fetch_response(response, deserialize_body)
+                    .await;
+            }
+        }
+
+        let response = self.send_request(request).await?;
+        self.response_to_fetch_response(response, deserialize_body)
+            .await
+    }
+
+    async fn execute_cached<T>(
+        &self,
+        cache: &HttpCache,
+        key: String,
+        mut request: Request,
+        deserialize_body: bool,
+    ) -> FetchResult<FetchResponse<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let cached = cache.lookup(&key, &request).await;
+        let now = SystemTime::now();
+
+        if let Some(cached) = &cached {
+            HttpCache::add_validators(&mut request, cached);
+            if !HttpCache::requires_revalidation(&request) {
+                if cached.is_fresh(now) {
+                    return self.cached_to_fetch_response(
+                        cached.clone(),
+                        CacheStatus::Hit,
+                        deserialize_body,
+                    );
+                }
+                if cached.may_serve_while_revalidating(now) {
+                    self.refresh_in_background(cache, key, request, cached.clone());
+                    return self.cached_to_fetch_response(
+                        cached.clone(),
+                        CacheStatus::Stale,
+                        deserialize_body,
+                    );
+                }
+            }
+        }
+
+        let err = match self.fill_cache(cache, &key, request, cached.clone()).await {
+            Ok(CacheFill::Revalidated(cached)) => {
+                return self.cached_to_fetch_response(
+                    cached,
+                    CacheStatus::Revalidated,
+                    deserialize_body,
+                )
+            }
+            Ok(CacheFill::Fetched {
+                status,
+                headers,
+                raw_body,
+                remote_address,
+            }) => {
+                return self.parts_to_fetch_response(
+                    status,
+                    &headers,
+                    raw_body,
+                    remote_address,
+                    deserialize_body,
+                )
+            }
+            Err(err) => err,
+        };
+
+        let serve_stale = cached.filter(|cached| match err.cause() {
+            FetchError::UnableToSendRequest { .. } | FetchError::Timeout { .. } => {
+                cache.offline_fallback || cached.may_serve_on_error(SystemTime::now())
+            }
+            FetchError::NetworkError(network_error) => {
+                matches!(network_error.status_code.as_u16(), 500 | 502 | 503 | 504)
+                    && cached.may_serve_on_error(SystemTime::now())
+            }
+            _ => false,
         });
+        match serve_stale {
+            Some(cached) => {
+                self.cached_to_fetch_response(cached, CacheStatus::Stale, deserialize_body)
+            }
+            None => Err(err),
+        }
+    }
+
+    /// Sends `request` and stores the outcome: a `304` refreshes `cached`, any other cacheable
+    /// response replaces it
+    async fn fill_cache(
+        &self,
+        cache: &HttpCache,
+        key: &str,
+        request: Request,
+        cached: Option<CachedResponse>,
+    ) -> FetchResult<CacheFill> {
+        let cache_request = request.try_clone();
+        let response = self.send_request(request).await?;
+
+        if let (Some(mut cached), Some(_)) = (cached, &cache_request) {
+            if response.status() == StatusCode::NOT_MODIFIED {
+                cached.revalidated(response.headers(), SystemTime::now());
+                cache.put(key, cached.clone()).await;
+                return Ok(CacheFill::Revalidated(cached));
+            }
+        }
+
+        let response = self.check_response_and_return_err(response).await?;
+        let headers = response.headers().clone();
+        let remote_address = response.remote_addr();
+        let status = response.status();
+        let raw_body = self.read_body(response).await?;
+
+        if let (Some(cache_request), Some(raw_body)) = (&cache_request, &raw_body) {
+            cache
+                .store(key, cache_request, status, &headers, raw_body)
+                .await;
+        }
+        Ok(CacheFill::Fetched {
+            status,
+            headers,
+            raw_body,
+            remote_address,
+        })
+    }
+
+    /// The key responses to `url` are cached under within `partition`, see `cache_partition`.
+    /// With an `EndpointSet` it leaves out the replica, so all replicas share one entry
+    fn cache_key(&self, partition: Option<&str>, method: &Method, url: &Url) -> String {
+        let endpoints = self.config.as_ref().and_then(|c| c.endpoints.as_ref());
+        let key = match endpoints.and_then(|endpoints| endpoints.relative(url.as_str())) {
+            Some(path) => format!("{method} endpoints:{path}"),
+            None => HttpCache::key(method, url),
+        };
+        match partition {
+            Some(partition) => format!("{partition} {key}"),
+            None => key,
+        }
+    }
+
+    /// A hash of the call's default, scoped and per-call `headers` and the identity of the
+    /// configured authentication, so tenants sharing a cache never see each other's responses,
+    /// even after `update_defaults` swapped their tenant or token headers
+    fn cache_partition(&self, headers: &HeaderMap) -> Option<String> {
+        let auth = self.config.as_ref().and_then(|c| c.auth.as_ref());
+        if headers.is_empty() && auth.is_none() {
+            return None;
+        }
+        let mut headers: Vec<Vec<u8>> = headers
+            .iter()
+            .map(|(name, value)| [name.as_str().as_bytes(), b": ", value.as_bytes()].concat())
+            .collect();
+        headers.sort();
+
+        let mut hasher = Sha256::new();
+        for header in headers {
+            hasher.update(&header);
+            hasher.update(b"\n");
+        }
+        if let Some(auth) = auth {
+            hasher.update(auth.identity().as_bytes());
+        }
+        Some(hex::encode(hasher.finalize()))
+    }
+
+    /// Refreshes `cached` on a background task, unless a refresh for it is already running
+    fn refresh_in_background(
+        &self,
+        cache: &HttpCache,
+        key: String,
+        request: Request,
+        cached: CachedResponse,
+    ) {
+        if !cache.begin_refresh(&key) {
+            return;
+        }
+        let fetch = self.clone();
+        let cache = cache.clone();
+        tokio::spawn(async move {
+            let _ = fetch.fill_cache(&cache, &key, request, Some(cached)).await;
+            cache.end_refresh(&key);
+        });
+    }
+
+    /// Sends `request`, retrying it according to the configured `RetryPolicy`
+    async fn send_request(&self, request: Request) -> FetchResult<Response> {
+        let retry = self.config.as_ref().and_then(|c| c.retry.as_ref());
+        let budget = retry.and_then(|retry| retry.budget.as_ref());
+        let deposit = |outcome: &FetchResult<Response>| {
+            if let (Some(budget), Ok(response)) = (budget, outcome) {
+                if !response.status().is_client_error() && !response.status().is_server_error() {
+                    budget.deposit();
+                }
+            }
+        };
+        let Some(retry) = retry.filter(|_| RetryPolicy::applies_to(&request)) else {
+            let outcome = self.send_authorized(request).await;
+            deposit(&outcome);
+            return outcome;
+        };
+
+        let mut request = request;
+        let mut attempt = 0;
+        loop {
+            let next = request.try_clone();
+            let outcome = self.send_authorized(request).await;
+            let retryable = match &outcome {
+                Ok(response) => RetryPolicy::is_retryable(response),
+                Err(err) => matches!(
+                    err,
+                    FetchError::UnableToSendRequest { .. } | FetchError::Timeout { .. }
+                ),
+            };
+            let delay = retry.backoff(attempt, outcome.as_ref().ok());
+            let (Some(next), Some(delay), true, true) =
+                (next, delay, retryable, attempt < retry.max_retries())
+            else {
+                deposit(&outcome);
+                return outcome;
+            };
+
+            if budget.is_some_and(|budget| !budget.try_withdraw()) {
+                let source = match outcome {
+                    Ok(response) => FetchError::NetworkError(NetworkError::new(response).await),
+                    Err(err) => err,
+                };
+                return Err(FetchError::RetryBudgetExhausted {
+                    attempts: attempt + 1,
+                    source: Box::new(source),
+                });
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            request = next;
+            if let Some(endpoints) = self.config.as_ref().and_then(|c| c.endpoints.as_ref()) {
+                endpoints.reroute(&mut request);
+            }
+        }
+    }
+
+    /// Sends `request` with the configured authentication, retrying once if the server asks to
+    /// authenticate again
+    async fn send_authorized(&self, request: Request) -> FetchResult<Response> {
+        let Some(auth) = self.config.as_ref().and_then(|c| c.auth.as_ref()) else {
+            return self.dispatch(request).await;
+        };
+
+        let retry = request.try_clone();
+        let mut request = request;
+        let authorization = self.authorize(auth, &mut request).await?;
+        let response = self.dispatch(request).await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            if let Some(mut retry) = retry {
+                if auth.unauthorized(&authorization, &response).await {
+                    self.authorize(auth, &mut retry).await?;
+                    return self.dispatch(retry).await;
+                }
+            }
+        }
+        Ok(response)
+    }
+
+    /// Authorizes `request`. Token requests made for it get the timeout of `request` itself
+    async fn authorize(
+        &self,
+        auth: &FetchAuth,
+        request: &mut Request,
+    ) -> FetchResult<Authorization> {
+        let Some(timeout) = request.timeout().copied() else {
+            return auth.authorize(&self.client, request).await;
+        };
+        tokio::time::timeout(timeout, auth.authorize(&self.client, request))
+            .await
+            .unwrap_or(Err(FetchError::Timeout {
+                phase: TimeoutPhase::Request,
+                after: timeout,
+            }))
+    }
+
+    async fn dispatch(&self, mut request: Request) -> FetchResult<Response> {
+        // An open circuit fails fast, before waiting for or spending any rate limit quota
+        let circuit_breaker = self.config.as_ref().and_then(|c| c.circuit_breaker.as_ref());
+        let url = request.url().clone();
+        if let Some(circuit_breaker) = circuit_breaker {
+            circuit_breaker.acquire(&url)?;
+        }
+        let throttle = self.config.as_ref().and_then(|c| c.throttle.as_ref());
+        let admitted = async {
+            if let Some(throttle) = throttle {
+                throttle.acquire(request.url()).await?;
+            }
+            if let Some(rate_limiter) = self.config.as_ref().and_then(|c| c.rate_limiter.as_ref()) {
+                rate_limiter.acquire(&request).await?;
+            }
+            for middleware in self.config.iter().flat_map(|c| &c.middleware) {
+                middleware.on_request(&mut request)?;
+            }
+            if let Some(signer) = self.config.as_ref().and_then(|c| c.signer.as_ref()) {
+                signer.sign(&mut request)?;
+            }
+            Ok::<_, FetchError>(())
+        }
+        .await;
+        if let Err(err) = admitted {
+            if let Some(circuit_breaker) = circuit_breaker {
+                circuit_breaker.release(&url);
+            }
+            return Err(err);
+        }
+        let in_flight = self
+            .config
+            .as_ref()
+            .and_then(|c| c.endpoints.as_ref())
+            .and_then(|endpoints| endpoints.begin(&url));
+        let attempt = Attempt {
+            started: Instant::now(),
+            timeout: request.timeout().copied(),
+            head: request.method() == Method::HEAD,
+        };
+        let response = self.execute_hedged(request).await;
+        let failed = response
+            .as_ref()
+            .map_or(true, |response| response.status().is_server_error());
+        if let Some(circuit_breaker) = circuit_breaker {
+            circuit_breaker.record_outcome(&url, failed);
+        }
+        if let Some(in_flight) = in_flight {
+            in_flight.finish(failed);
+        }
+        let mut response = response.map_err(|e| match self.timeout_error(&e, attempt) {
+            Some(timeout) => timeout,
+            None => FetchError::UnableToSendRequest { err: e },
+        })?;
+        response.extensions_mut().insert(attempt);
+        for middleware in self.config.iter().flat_map(|c| &c.middleware).rev() {
+            middleware.on_response(&mut response)?;
+        }
+
+        if let Some(throttle) = throttle {
+            throttle.observe(response.url(), response.status(), response.headers());
+        }
+        Ok(response)
+    }
+
+    /// Executes `request`, sending a hedge if the configured `HedgePolicy` allows it and the first
+    /// attempt is slow. The first usable response wins and the other attempt is dropped
+    async fn execute_hedged(&self, request: Request) -> Result<Response, reqwest::Error> {
+        let Some(hedging) = self.config.as_ref().and_then(|c| c.hedging.as_ref()) else {
+            return self.client.execute(request).await;
+        };
+        hedging.deposit();
+        let hedge = request
+            .try_clone()
+            .filter(|_| hedging.applies_to(request.method()));
+        let Some(hedge) = hedge else {
+            return self.client.execute(request).await;
+        };
+
+        let first = self.client.execute(request);
+        tokio::pin!(first);
+        tokio::select! {
+            response = &mut first => return response,
+            _ = tokio::time::sleep(hedging.delay) => {}
+        }
+        if !hedging.try_spend() {
+            return first.await;
+        }
+        // The hedge is a request of its own: it needs a free slot in the bulkhead and rate limit
+        // budget, but it never waits for one
+        let permit = match self.config.as_ref().and_then(|c| c.bulkhead.as_ref()) {
+            Some(bulkhead) => match bulkhead.try_acquire(&hedge) {
+                Ok(permit) => permit,
+                Err(_) => return first.await,
+            },
+            None => None,
+        };
+        let rate_limiter = self.config.as_ref().and_then(|c| c.rate_limiter.as_ref());
+        if !rate_limiter.is_none_or(|limiter| limiter.try_acquire(&hedge)) {
+            return first.await;
+        }
+
+        let second = async move {
+            let response = self.client.execute(hedge).await;
+            drop(permit);
+            response
+        };
+        tokio::pin!(second);
+        let usable = |response: &Result<Response, reqwest::Error>| {
+            response.as_ref().is_ok_and(|response| {
+                !response.status().is_server_error()
+                    && response.status() != StatusCode::TOO_MANY_REQUESTS
+            })
+        };
+        tokio::select! {
+            response = &mut first => match response {
+                response if usable(&response) => response,
+                response => second.await.or(response),
+            },
+            response = &mut second => match response {
+                response if usable(&response) => response,
+                response => first.await.or(response),
+            },
+        }
+    }
+
+    /// Starts building a request with the given method, to be sent with `FetchRequestBuilder::send`
+    ///
+    /// * `endpoint` - The remote endpoint. This gets joined with the base_url configured in the ::new() method
+    ///
+    /// # Example
+    /// ```rust
+    /// use rust_fetch::{reqwest::Method, Fetch};
+    ///
+    /// let fetch = Fetch::new("http://localhost", None).unwrap();
+    /// let request = fetch
+    ///     .request(Method::GET, "/orders")
+    ///     .query("status", "open")
+    ///     .header("x-tenant", "acme");
+    /// ```
+    pub fn request(&self, method: Method, endpoint: &str) -> FetchRequestBuilder<'_> {
+        FetchRequestBuilder::new(self, method, endpoint)
+    }
+
+    /// Starts building a `GET` request, see `Fetch::request`.
+    ///
+    /// `get`, `post` and the other method-named calls keep sending right away with
+    /// `FetchOptions`, so existing callers keep compiling. The builder entry points are named
+    /// after the method with a `_request` suffix instead.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rust_fetch::Fetch;
+    ///
+    /// let fetch = Fetch::new("http://localhost", None).unwrap();
+    /// let request = fetch.get_request("/orders").query("status", "open");
+    /// ```
+    pub fn get_request(&self, endpoint: &str) -> FetchRequestBuilder<'_> {
+        self.request(Method::GET, endpoint)
+    }
+
+    /// Starts building a `POST` request, see `Fetch::get_request`
+    pub fn post_request(&self, endpoint: &str) -> FetchRequestBuilder<'_> {
+        self.request(Method::POST, endpoint)
+    }
+
+    /// Starts building a `PUT` request, see `Fetch::get_request`
+    pub fn put_request(&self, endpoint: &str) -> FetchRequestBuilder<'_> {
+        self.request(Method::PUT, endpoint)
+    }
+
+    /// Starts building a `PATCH` request, see `Fetch::get_request`
+    pub fn patch_request(&self, endpoint: &str) -> FetchRequestBuilder<'_> {
+        self.request(Method::PATCH, endpoint)
+    }
+
+    /// Starts building a `DELETE` request, see `Fetch::get_request`
+    pub fn delete_request(&self, endpoint: &str) -> FetchRequestBuilder<'_> {
+        self.request(Method::DELETE, endpoint)
     }
 
     /// Sends an HTTP Post request to the configured remote server
@@ -371,20 +1329,8 @@ impl Fetch {
         T: for<'de> Deserialize<'de>,
         U: Serialize,
     {
-        let options = options.unwrap_or_default();
-        let response = self
-            .build_request(
-                data,
-                Some(&options),
-                self.client.post(self.build_url(endpoint, Some(&options))?),
-            )?
-            .send()
+        self.execute(Method::POST, endpoint, data, options)
             .await
-            .map_err(|e| FetchError::UnableToSendRequest { err: e })?;
-
-        return Ok(self
-            .response_to_fetch_response(response, options.deserialize_body)
-            .await?);
     }
 
     /// Sends an HTTP GET request to the configured remote server
@@ -432,20 +1378,8 @@ impl Fetch {
     where
         T: for<'de> Deserialize<'de>,
     {
-        let options = options.unwrap_or_default();
-        let response = self
-            .build_request::<()>(
-                None,
-                Some(&options),
-                self.client.get(self.build_url(endpoint, Some(&options))?),
-            )?
-            .send()
+        self.execute::<T, ()>(Method::GET, endpoint, None, options)
             .await
-            .map_err(|e| FetchError::UnableToSendRequest { err: e })?;
-
-        return Ok(self
-            .response_to_fetch_response(response, options.deserialize_body)
-            .await?);
     }
 
     /// Sends an HTTP DELETE request to the configured remote server
@@ -488,21 +1422,8 @@ impl Fetch {
         T: Serialize,
         U: for<'de> Deserialize<'de>,
     {
-        let options = options.unwrap_or_default();
-        let response = self
-            .build_request(
-                data,
-                Some(&options),
-                self.client
-                    .delete(self.build_url(endpoint, Some(&options))?),
-            )?
-            .send()
+        self.execute(Method::DELETE, endpoint, data, options)
             .await
-            .map_err(|e| FetchError::UnableToSendRequest { err: e })?;
-
-        return Ok(self
-            .response_to_fetch_response(response, options.deserialize_body)
-            .await?);
     }
 
     /// Sends an HTTP PUT request to the configured remote server
@@ -545,20 +1466,8 @@ impl Fetch {
         T: Serialize,
         U: for<'de> Deserialize<'de>,
     {
-        let options = options.unwrap_or_default();
-        let response = self
-            .build_request(
-                data,
-                Some(&options),
-                self.client.put(self.build_url(endpoint, Some(&options))?),
-            )?
-            .send()
+        self.execute(Method::PUT, endpoint, data, options)
             .await
-            .map_err(|e| FetchError::UnableToSendRequest { err: e })?;
-
-        return Ok(self
-            .response_to_fetch_response(response, options.deserialize_body)
-            .await?);
     }
 
     /// Sends an HTTP PATCH request to the configured remote server
@@ -601,19 +1510,77 @@ impl Fetch {
         T: Serialize,
         U: for<'de> Deserialize<'de>,
     {
-        let options = options.unwrap_or_default();
-        let response = self
-            .build_request(
-                data,
-                Some(&options),
-                self.client.patch(self.build_url(endpoint, Some(&options))?),
-            )?
-            .send()
+        self.execute(Method::PATCH, endpoint, data, options)
             .await
-            .map_err(|e| FetchError::UnableToSendRequest { err: e })?;
+    }
+
+    /// Sends an HTTP GET request and returns the body together with its `ResourceVersion`, so it
+    /// can later be updated with `put_versioned` or `patch_versioned`
+    ///
+    /// * `endpoint` - The remote endpoint. This gets joined with the base_url configured in the ::new() method
+    /// * `options` - The `FetchOptions` for this call. Allows setting of headers and/or query params
+    pub async fn get_versioned<T>(
+        &self,
+        endpoint: &str,
+        options: Option<FetchOptions>,
+    ) -> FetchResult<Versioned<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let options = FetchOptions {
+            deserialize_body: true,
+            ..options.unwrap_or_default()
+        };
+        let response = self.get::<T>(endpoint, Some(options)).await?;
+        let version = response.version();
+        let value = response.body.ok_or_else(|| {
+            FetchError::DeserializationError(DeserializationError::Unknown(
+                "Response has no body".to_string(),
+            ))
+        })?;
+        Ok(Versioned { value, version })
+    }
+
+    /// Sends `resource.value` in an HTTP PUT request that only succeeds if the resource is still
+    /// at `resource.version`. Otherwise `FetchError::PreconditionFailed` is returned.
+    ///
+    /// * `endpoint` - The remote endpoint. This gets joined with the base_url configured in the ::new() method
+    /// * `resource` - The resource as returned by `get_versioned`, with any changes applied
+    /// * `options` - The `FetchOptions` for this call. Allows setting of headers and/or query params
+    pub async fn put_versioned<T, U>(
+        &self,
+        endpoint: &str,
+        resource: &Versioned<T>,
+        options: Option<FetchOptions>,
+    ) -> FetchResult<FetchResponse<U>>
+    where
+        T: Serialize,
+        U: for<'de> Deserialize<'de>,
+    {
+        let options = resource.version.precondition(options)?;
+        self.put(endpoint, Some(&resource.value), Some(options))
+            .await
+    }
 
-        return Ok(self
-            .response_to_fetch_response(response, options.deserialize_body)
-            .await?);
+    /// Sends an HTTP PATCH request that only succeeds if the resource is still at `version`.
+    /// Otherwise `FetchError::PreconditionFailed` is returned.
+    ///
+    /// * `endpoint` - The remote endpoint. This gets joined with the base_url configured in the ::new() method
+    /// * `version` - The version the patch was computed against
+    /// * `data` - The optional data to send the the remote endpoint
+    /// * `options` - The `FetchOptions` for this call. Allows setting of headers and/or query params
+    pub async fn patch_versioned<T, U>(
+        &self,
+        endpoint: &str,
+        version: &ResourceVersion,
+        data: Option<T>,
+        options: Option<FetchOptions>,
+    ) -> FetchResult<FetchResponse<U>>
+    where
+        T: Serialize,
+        U: for<'de> Deserialize<'de>,
+    {
+        let options = version.precondition(options)?;
+        self.patch(endpoint, data, Some(options)).await
     }
 }
\ No newline at end of file
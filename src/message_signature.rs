@@ -0,0 +1,657 @@
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ed25519_dalek::{Signer, Verifier};
+use reqwest::{header::HeaderValue, Request};
+
+use crate::{
+    error::{FetchError, FetchResult},
+    signing::RequestSigner,
+    utils::hmac_sha256,
+    FetchHeaders, FetchResponse,
+};
+
+/// Signature algorithms from the HTTP Signature Algorithms registry of RFC 9421
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    HmacSha256,
+    Ed25519,
+}
+
+impl std::fmt::Display for SignatureAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureAlgorithm::HmacSha256 => write!(f, "hmac-sha256"),
+            SignatureAlgorithm::Ed25519 => write!(f, "ed25519"),
+        }
+    }
+}
+
+/// Key material used to create and verify HTTP message signatures.
+///
+/// Implement this to load keys from a secret store; `HmacSha256Key` and `Ed25519Key` cover keys
+/// that are already in memory.
+pub trait SignatureKey: Debug + Send + Sync {
+    /// The `keyid` parameter sent to the server
+    fn key_id(&self) -> &str;
+    fn algorithm(&self) -> SignatureAlgorithm;
+    fn sign(&self, data: &[u8]) -> FetchResult<Vec<u8>>;
+    fn verify(&self, data: &[u8], signature: &[u8]) -> FetchResult<bool>;
+}
+
+/// A shared secret for `hmac-sha256` signatures
+#[derive(Clone)]
+pub struct HmacSha256Key {
+    key_id: String,
+    secret: Vec<u8>,
+}
+
+impl Debug for HmacSha256Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HmacSha256Key")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HmacSha256Key {
+    pub fn new(key_id: &str, secret: &[u8]) -> Self {
+        Self {
+            key_id: key_id.to_string(),
+            secret: secret.to_vec(),
+        }
+    }
+}
+
+impl SignatureKey for HmacSha256Key {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::HmacSha256
+    }
+
+    fn sign(&self, data: &[u8]) -> FetchResult<Vec<u8>> {
+        Ok(hmac_sha256(&self.secret, data))
+    }
+
+    fn verify(&self, data: &[u8], signature: &[u8]) -> FetchResult<bool> {
+        let expected = hmac_sha256(&self.secret, data);
+        // Compare in constant time
+        Ok(expected.len() == signature.len()
+            && expected
+                .iter()
+                .zip(signature)
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                == 0)
+    }
+}
+
+/// An Ed25519 key pair, or only a public key when used for verification
+#[derive(Clone)]
+pub struct Ed25519Key {
+    key_id: String,
+    signing_key: Option<ed25519_dalek::SigningKey>,
+    verifying_key: ed25519_dalek::VerifyingKey,
+}
+
+impl Debug for Ed25519Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ed25519Key")
+            .field("key_id", &self.key_id)
+            .field("verifying_key", &self.verifying_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Ed25519Key {
+    /// Creates a key pair from a 32 byte secret key
+    pub fn from_secret(key_id: &str, secret: &[u8; 32]) -> Self {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(secret);
+        Self {
+            key_id: key_id.to_string(),
+            verifying_key: signing_key.verifying_key(),
+            signing_key: Some(signing_key),
+        }
+    }
+
+    /// Creates a verification-only key from a 32 byte public key
+    pub fn from_public(key_id: &str, public: &[u8; 32]) -> FetchResult<Self> {
+        Ok(Self {
+            key_id: key_id.to_string(),
+            signing_key: None,
+            verifying_key: ed25519_dalek::VerifyingKey::from_bytes(public)
+                .map_err(|e| FetchError::SigningError(e.to_string()))?,
+        })
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.verifying_key.to_bytes()
+    }
+}
+
+impl SignatureKey for Ed25519Key {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::Ed25519
+    }
+
+    fn sign(&self, data: &[u8]) -> FetchResult<Vec<u8>> {
+        let signing_key = self.signing_key.as_ref().ok_or_else(|| {
+            FetchError::SigningError(format!("{} is a verification-only key", self.key_id))
+        })?;
+        Ok(signing_key.sign(data).to_bytes().to_vec())
+    }
+
+    fn verify(&self, data: &[u8], signature: &[u8]) -> FetchResult<bool> {
+        let Ok(signature) = ed25519_dalek::Signature::from_slice(signature) else {
+            return Ok(false);
+        };
+        Ok(self.verifying_key.verify(data, &signature).is_ok())
+    }
+}
+
+/// A component covered by an HTTP message signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureComponent {
+    /// `@method`
+    Method,
+    /// `@target-uri`
+    TargetUri,
+    /// `@authority`
+    Authority,
+    /// `@path`
+    Path,
+    /// `@query`
+    Query,
+    /// `@status`, only available on responses
+    Status,
+    /// A header field, e.g. `date` or `content-digest`
+    Header(String),
+}
+
+impl SignatureComponent {
+    pub fn header(name: &str) -> Self {
+        Self::Header(name.to_ascii_lowercase())
+    }
+
+    fn parse(identifier: &str) -> Self {
+        match identifier {
+            "@method" => Self::Method,
+            "@target-uri" => Self::TargetUri,
+            "@authority" => Self::Authority,
+            "@path" => Self::Path,
+            "@query" => Self::Query,
+            "@status" => Self::Status,
+            name => Self::header(name),
+        }
+    }
+}
+
+impl std::fmt::Display for SignatureComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Method => write!(f, "@method"),
+            Self::TargetUri => write!(f, "@target-uri"),
+            Self::Authority => write!(f, "@authority"),
+            Self::Path => write!(f, "@path"),
+            Self::Query => write!(f, "@query"),
+            Self::Status => write!(f, "@status"),
+            Self::Header(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Builds the signature base of RFC 9421 section 2.5 from already resolved component values
+fn signature_base(components: &[(SignatureComponent, String)], params: &str) -> String {
+    let mut base = String::new();
+    for (component, value) in components {
+        base += &format!("\"{component}\": {value}\n");
+    }
+    base += &format!("\"@signature-params\": {params}");
+    base
+}
+
+/// Signs requests with HTTP Message Signatures (RFC 9421), adding `Signature-Input` and
+/// `Signature` headers.
+///
+/// Every covered header must be present on the request, except `date` which is added when
+/// missing. Signing a request that lacks a covered header fails with `FetchError::SigningError`.
+///
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use rust_fetch::{Fetch, FetchConfig, HmacSha256Key, MessageSigner, SignatureComponent};
+///
+/// let signer = MessageSigner::new(Arc::new(HmacSha256Key::new("partner-key", b"secret")))
+///     .with_components([
+///         SignatureComponent::Method,
+///         SignatureComponent::Path,
+///         SignatureComponent::Query,
+///         SignatureComponent::header("date"),
+///     ]);
+/// let client = Fetch::new(
+///     "https://partner.local",
+///     Some(FetchConfig {
+///         signer: Some(Arc::new(signer)),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct MessageSigner {
+    key: Arc<dyn SignatureKey>,
+    label: String,
+    components: Vec<SignatureComponent>,
+    include_algorithm: bool,
+}
+
+impl MessageSigner {
+    /// Creates a signer covering `@method`, `@authority` and `@path` with the label `sig1`
+    pub fn new(key: Arc<dyn SignatureKey>) -> Self {
+        Self {
+            key,
+            label: "sig1".to_string(),
+            components: vec![
+                SignatureComponent::Method,
+                SignatureComponent::Authority,
+                SignatureComponent::Path,
+            ],
+            include_algorithm: false,
+        }
+    }
+
+    pub fn with_components<I>(mut self, components: I) -> Self
+    where
+        I: IntoIterator<Item = SignatureComponent>,
+    {
+        self.components = components.into_iter().collect();
+        self
+    }
+
+    /// Sets the label used in the `Signature-Input` and `Signature` dictionaries
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = label.to_string();
+        self
+    }
+
+    /// Adds the `alg` parameter to the signature parameters
+    pub fn with_algorithm_parameter(mut self, include_algorithm: bool) -> Self {
+        self.include_algorithm = include_algorithm;
+        self
+    }
+
+    fn sign_at(&self, request: &mut Request, created: u64) -> FetchResult<()> {
+        let covers_date = self
+            .components
+            .contains(&SignatureComponent::Header("date".to_string()));
+        if covers_date && !request.headers().contains_key(reqwest::header::DATE) {
+            let date = httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(created));
+            request
+                .headers_mut()
+                .insert(reqwest::header::DATE, header_value(&date)?);
+        }
+
+        let mut components = Vec::new();
+        for component in &self.components {
+            let value = request_component(request, component)?.ok_or_else(|| {
+                FetchError::SigningError(format!("covered component {component} is missing"))
+            })?;
+            components.push((component.clone(), value));
+        }
+
+        let mut params = format!(
+            "({});created={created};keyid=\"{}\"",
+            components
+                .iter()
+                .map(|(component, _)| format!("\"{component}\""))
+                .collect::<Vec<_>>()
+                .join(" "),
+            self.key.key_id()
+        );
+        if self.include_algorithm {
+            params += &format!(";alg=\"{}\"", self.key.algorithm());
+        }
+
+        let signature = self.key.sign(signature_base(&components, &params).as_bytes())?;
+        let headers = request.headers_mut();
+        headers.insert(
+            "signature-input",
+            header_value(&format!("{}={params}", self.label))?,
+        );
+        headers.insert(
+            "signature",
+            header_value(&format!("{}=:{}:", self.label, STANDARD.encode(signature)))?,
+        );
+        Ok(())
+    }
+}
+
+impl RequestSigner for MessageSigner {
+    fn sign(&self, request: &mut Request) -> FetchResult<()> {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| FetchError::SigningError(e.to_string()))?
+            .as_secs();
+        self.sign_at(request, created)
+    }
+}
+
+fn header_value(value: &str) -> FetchResult<HeaderValue> {
+    HeaderValue::from_str(value).map_err(|e| FetchError::SigningError(e.to_string()))
+}
+
+fn request_component(
+    request: &Request,
+    component: &SignatureComponent,
+) -> FetchResult<Option<String>> {
+    let url = request.url();
+    Ok(match component {
+        SignatureComponent::Method => Some(request.method().to_string()),
+        SignatureComponent::TargetUri => Some(url.to_string()),
+        SignatureComponent::Authority => {
+            let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+            Some(match url.port() {
+                Some(port) => format!("{host}:{port}"),
+                None => host,
+            })
+        }
+        SignatureComponent::Path => Some(url.path().to_string()),
+        SignatureComponent::Query => Some(format!("?{}", url.query().unwrap_or_default())),
+        SignatureComponent::Status => {
+            return Err(FetchError::SigningError(
+                "@status cannot be used to sign requests".to_string(),
+            ))
+        }
+        SignatureComponent::Header(name) => {
+            let values = request
+                .headers()
+                .get_all(name.as_str())
+                .iter()
+                .map(|value| value.to_str().map(str::trim))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| FetchError::SigningError(e.to_string()))?;
+            (!values.is_empty()).then(|| values.join(", "))
+        }
+    })
+}
+
+/// Verifies HTTP message signatures on responses (RFC 9421).
+///
+/// Responses may cover `@status` and any header field. Signatures past their `expires` parameter,
+/// or `created` further in the future than the allowed clock skew (60 seconds by default), are
+/// rejected. Set a maximum age to also reject old signatures that could be replayed.
+#[derive(Debug, Clone)]
+pub struct MessageVerifier {
+    key: Arc<dyn SignatureKey>,
+    label: String,
+    clock_skew: Duration,
+    max_age: Option<Duration>,
+}
+
+impl MessageVerifier {
+    /// Creates a verifier for signatures with the label `sig1`
+    pub fn new(key: Arc<dyn SignatureKey>) -> Self {
+        Self {
+            key,
+            label: "sig1".to_string(),
+            clock_skew: Duration::from_secs(60),
+            max_age: None,
+        }
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = label.to_string();
+        self
+    }
+
+    /// How far the clocks of the signer and this client may drift apart
+    pub fn with_clock_skew(mut self, clock_skew: Duration) -> Self {
+        self.clock_skew = clock_skew;
+        self
+    }
+
+    /// Rejects signatures created longer than `max_age` ago
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Verifies the signature of `response`, failing with `FetchError::InvalidSignature`
+    pub fn verify<T>(&self, response: &FetchResponse<T>) -> FetchResult<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| FetchError::InvalidSignature(e.to_string()))?
+            .as_secs();
+        self.verify_parts(response.status.as_u16(), &response.response_headers, now)
+    }
+
+    /// Checks the `created` and `expires` parameters against `now`, in seconds since the epoch
+    fn check_validity(&self, params: &str, now: u64) -> FetchResult<()> {
+        let invalid = |reason: &str| FetchError::InvalidSignature(reason.to_string());
+        let timestamp = |name: &str| -> FetchResult<Option<u64>> {
+            param(params, name)
+                .map(|value| value.parse())
+                .transpose()
+                .map_err(|_| invalid(&format!("malformed {name} parameter")))
+        };
+        let skew = self.clock_skew.as_secs();
+
+        if let Some(created) = timestamp("created")? {
+            if created > now.saturating_add(skew) {
+                return Err(invalid("signature created in the future"));
+            }
+            if let Some(max_age) = self.max_age {
+                if now.saturating_sub(created) > max_age.as_secs().saturating_add(skew) {
+                    return Err(invalid("signature is too old"));
+                }
+            }
+        } else if self.max_age.is_some() {
+            return Err(invalid("signature has no created parameter"));
+        }
+        if let Some(expires) = timestamp("expires")? {
+            if now > expires.saturating_add(skew) {
+                return Err(invalid("signature has expired"));
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_parts(&self, status: u16, headers: &FetchHeaders, now: u64) -> FetchResult<()> {
+        let invalid = |reason: &str| FetchError::InvalidSignature(reason.to_string());
+
+        let signature_input = headers
+            .get("signature-input")
+            .ok_or_else(|| invalid("missing Signature-Input header"))?;
+        let signature = headers
+            .get("signature")
+            .ok_or_else(|| invalid("missing Signature header"))?;
+
+        let params = dictionary_member(signature_input, &self.label)
+            .ok_or_else(|| invalid("no Signature-Input for this label"))?;
+        let signature = dictionary_member(signature, &self.label)
+            .and_then(|value| value.strip_prefix(':')?.strip_suffix(':'))
+            .ok_or_else(|| invalid("no Signature for this label"))?;
+        let signature = STANDARD
+            .decode(signature)
+            .map_err(|_| invalid("Signature is not valid base64"))?;
+
+        let key_id = param(params, "keyid");
+        if key_id.as_deref().is_some_and(|key_id| key_id != self.key.key_id()) {
+            return Err(invalid("signed with an unknown keyid"));
+        }
+        self.check_validity(params, now)?;
+
+        let inner_list = params
+            .strip_prefix('(')
+            .and_then(|rest| rest.split_once(')'))
+            .map(|(list, _)| list)
+            .ok_or_else(|| invalid("malformed Signature-Input"))?;
+        let mut components = Vec::new();
+        for identifier in inner_list.split_whitespace() {
+            let component = SignatureComponent::parse(identifier.trim_matches('"'));
+            let value = match &component {
+                SignatureComponent::Status => status.to_string(),
+                SignatureComponent::Header(name) => headers
+                    .get(name)
+                    .map(|value| value.trim().to_string())
+                    .ok_or_else(|| invalid(&format!("covered header {name} is missing")))?,
+                _ => return Err(invalid(&format!("{component} cannot be verified on a response"))),
+            };
+            components.push((component, value));
+        }
+
+        if self
+            .key
+            .verify(signature_base(&components, params).as_bytes(), &signature)?
+        {
+            Ok(())
+        } else {
+            Err(invalid("signature does not match"))
+        }
+    }
+}
+
+/// Returns the raw value of `label` in a structured field dictionary
+fn dictionary_member<'a>(dictionary: &'a str, label: &str) -> Option<&'a str> {
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 0;
+    let mut members = Vec::new();
+    for (index, c) in dictionary.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                members.push(&dictionary[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    members.push(&dictionary[start..]);
+
+    members.into_iter().find_map(|member| {
+        let (key, value) = member.trim().split_once('=')?;
+        (key == label).then_some(value)
+    })
+}
+
+/// Returns the value of a `;name=value` signature parameter, without quotes
+fn param(params: &str, name: &str) -> Option<String> {
+    params.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        (key == name).then(|| value.trim_matches('"').to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    use super::{
+        dictionary_member, signature_base, HmacSha256Key, MessageVerifier, SignatureComponent,
+        SignatureKey,
+    };
+    use crate::map_string;
+
+    // Example from RFC 9421 appendix B.2.5
+    const SHARED_SECRET: &str =
+        "uzvJfB4u3N0Jy4T7NZ75MDVcr8zSTInedJtkgcu46YW4XByzNJjxBdtjUkdJPBtbmHhIDi6pcl8jsasjlTMtDQ==";
+
+    #[test]
+    fn test_rfc9421_hmac_sha256_signature() {
+        let key = HmacSha256Key::new("test-shared-secret", &STANDARD.decode(SHARED_SECRET).unwrap());
+        let base = signature_base(
+            &[
+                (
+                    SignatureComponent::header("date"),
+                    "Tue, 20 Apr 2021 02:07:55 GMT".to_string(),
+                ),
+                (SignatureComponent::Authority, "example.com".to_string()),
+                (
+                    SignatureComponent::header("content-type"),
+                    "application/json".to_string(),
+                ),
+            ],
+            r#"("date" "@authority" "content-type");created=1618884473;keyid="test-shared-secret""#,
+        );
+
+        assert_eq!(
+            "pxcQw6G3AjtMBQjwo8XzkZf/bws5LelbaMk5rGIGtE8=",
+            STANDARD.encode(key.sign(base.as_bytes()).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_dictionary_member_ignores_commas_in_strings() {
+        let dictionary = r#"sig0=("@path");keyid="a,b", sig1=("@status");created=1"#;
+        assert_eq!(
+            Some(r#"("@status");created=1"#),
+            dictionary_member(dictionary, "sig1")
+        );
+    }
+
+    #[test]
+    fn test_verify_response_signature() {
+        let key = Arc::new(HmacSha256Key::new("server-key", b"secret"));
+        let params = r#"("@status" "content-type");created=1618884473;keyid="server-key""#;
+        let base = signature_base(
+            &[
+                (SignatureComponent::Status, "200".to_string()),
+                (
+                    SignatureComponent::header("content-type"),
+                    "application/json".to_string(),
+                ),
+            ],
+            params,
+        );
+        let signature = STANDARD.encode(key.sign(base.as_bytes()).unwrap());
+        let mut headers = map_string! {
+            "content-type" => "application/json",
+            "signature-input" => format!("sig1={params}"),
+            "signature" => format!("sig1=:{signature}:"),
+        };
+
+        let verifier = MessageVerifier::new(key);
+        assert!(verifier.verify_parts(200, &headers, 1618884473).is_ok());
+        assert!(verifier.verify_parts(201, &headers, 1618884473).is_err());
+
+        headers.insert("content-type".to_string(), "text/plain".to_string());
+        assert!(verifier.verify_parts(200, &headers, 1618884473).is_err());
+    }
+
+    #[test]
+    fn test_verify_checks_created_and_expires() {
+        let key = Arc::new(HmacSha256Key::new("server-key", b"secret"));
+        let params = r#"("@status");created=1000;expires=1300;keyid="server-key""#;
+        let base = signature_base(&[(SignatureComponent::Status, "200".to_string())], params);
+        let signature = STANDARD.encode(key.sign(base.as_bytes()).unwrap());
+        let headers = map_string! {
+            "signature-input" => format!("sig1={params}"),
+            "signature" => format!("sig1=:{signature}:"),
+        };
+
+        let verifier = MessageVerifier::new(key).with_clock_skew(Duration::from_secs(10));
+        assert!(verifier.verify_parts(200, &headers, 1100).is_ok());
+        assert!(verifier.verify_parts(200, &headers, 995).is_ok());
+        assert!(verifier.verify_parts(200, &headers, 900).is_err());
+        assert!(verifier.verify_parts(200, &headers, 1311).is_err());
+
+        let verifier = verifier.with_max_age(Duration::from_secs(60));
+        assert!(verifier.verify_parts(200, &headers, 1050).is_ok());
+        assert!(verifier.verify_parts(200, &headers, 1100).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt::Debug;
+
+use reqwest::{Request, Response};
+
+use crate::error::FetchResult;
+
+/// Hooks into every attempt a `Fetch` sends, e.g. to add tracing headers, log, or collect
+/// metrics. Added with `FetchBuilder::middleware` or `FetchConfig::middleware`.
+///
+/// Middleware forms a chain: requests pass through it in the order it was added, responses in
+/// the reverse order. Requests are seen after throttling and rate limiting and before they are
+/// signed, so signatures cover the changes. Both hooks run again for every retry. Returning an
+/// error (e.g. `FetchError::MiddlewareError`) fails the attempt with it.
+///
+/// # Example
+/// ```rust
+/// use std::sync::{
+///     atomic::{AtomicUsize, Ordering},
+///     Arc,
+/// };
+/// use rust_fetch::{
+///     reqwest::{header::HeaderValue, Request, Response},
+///     Fetch, FetchResult, Middleware,
+/// };
+///
+/// #[derive(Debug, Default)]
+/// struct Tracing {
+///     sent: AtomicUsize,
+/// }
+///
+/// impl Middleware for Tracing {
+///     fn on_request(&self, request: &mut Request) -> FetchResult<()> {
+///         let id = self.sent.fetch_add(1, Ordering::SeqCst);
+///         request
+///             .headers_mut()
+///             .insert("x-request-id", HeaderValue::from(id));
+///         Ok(())
+///     }
+/// }
+///
+/// let client = Fetch::builder()
+///     .base_url("http://localhost")
+///     .middleware(Arc::new(Tracing::default()))
+///     .build();
+/// assert!(client.is_ok());
+/// ```
+pub trait Middleware: Debug + Send + Sync {
+    /// Called with every request before it is signed and sent
+    fn on_request(&self, _request: &mut Request) -> FetchResult<()> {
+        Ok(())
+    }
+
+    /// Called with every response before its status is checked and its body is read
+    fn on_response(&self, _response: &mut Response) -> FetchResult<()> {
+        Ok(())
+    }
+}
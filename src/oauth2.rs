@@ -0,0 +1,636 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex as StdMutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::error::{FetchError, FetchResult, OAuth2Error};
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// How the client authenticates itself against the token endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ClientAuthMethod {
+    /// Send `client_id` and `client_secret` using HTTP Basic auth (`client_secret_basic`)
+    #[default]
+    Basic,
+    /// Send `client_id` and `client_secret` as form fields in the request body (`client_secret_post`)
+    RequestBody,
+}
+
+/// An access token handed out by a token endpoint
+#[derive(Debug, Clone)]
+pub struct AccessToken {
+    pub access_token: String,
+    pub token_type: String,
+    pub refresh_token: Option<String>,
+    pub scope: Option<String>,
+    /// When the token expires, if the token endpoint sent `expires_in`
+    pub expires_at: Option<SystemTime>,
+}
+
+impl AccessToken {
+    /// Returns true if the token expires within `margin` from now
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        self.expires_at
+            .map(|expires_at| expires_at <= SystemTime::now() + margin)
+            .unwrap_or(false)
+    }
+}
+
+/// Persists tokens obtained by a `RefreshTokenGrant`, e.g. in a keychain or secret store.
+///
+/// `save` is called every time the token endpoint hands out a new token, so rotated refresh
+/// tokens are never lost.
+pub trait TokenStore: Send + Sync {
+    fn load(&self) -> Option<AccessToken>;
+    fn save(&self, token: &AccessToken);
+}
+
+/// A `TokenStore` that only keeps the latest token in memory
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    token: StdMutex<Option<AccessToken>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new(token: Option<AccessToken>) -> Self {
+        Self {
+            token: StdMutex::new(token),
+        }
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> Option<AccessToken> {
+        self.token.lock().unwrap().clone()
+    }
+
+    fn save(&self, token: &AccessToken) {
+        *self.token.lock().unwrap() = Some(token.clone());
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    token_type: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+impl From<TokenResponse> for AccessToken {
+    fn from(response: TokenResponse) -> Self {
+        Self {
+            access_token: response.access_token,
+            token_type: response.token_type.unwrap_or_else(|| "Bearer".to_string()),
+            refresh_token: response.refresh_token,
+            scope: response.scope,
+            expires_at: response
+                .expires_in
+                .map(|secs| SystemTime::now() + Duration::from_secs(secs)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TokenErrorResponse {
+    pub(crate) error: String,
+    #[serde(default)]
+    pub(crate) error_description: Option<String>,
+}
+
+/// The client id/secret pair and how it is presented to the authorization server
+#[derive(Clone)]
+struct ClientAuthentication {
+    client_id: String,
+    client_secret: Option<String>,
+    method: ClientAuthMethod,
+}
+
+impl Debug for ClientAuthentication {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientAuthentication")
+            .field("client_id", &self.client_id)
+            .field("method", &self.method)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClientAuthentication {
+    /// Adds the client fields to `form` and returns the Basic auth credentials, if any.
+    /// Public clients (no secret) always send their `client_id` in the body
+    fn apply<'a>(&'a self, form: &mut Vec<(&'a str, &'a str)>) -> Option<(&'a str, &'a str)> {
+        match (&self.client_secret, &self.method) {
+            (Some(secret), ClientAuthMethod::Basic) => Some((&self.client_id, secret)),
+            (Some(secret), ClientAuthMethod::RequestBody) => {
+                form.push(("client_id", &self.client_id));
+                form.push(("client_secret", secret));
+                None
+            }
+            (None, _) => {
+                form.push(("client_id", &self.client_id));
+                None
+            }
+        }
+    }
+}
+
+/// Posts `form` to an authorization server endpoint and returns the raw success body
+async fn post_form(
+    client: &Client,
+    url: &str,
+    auth: &ClientAuthentication,
+    mut form: Vec<(&str, &str)>,
+) -> FetchResult<bytes::Bytes> {
+    let credentials = auth.apply(&mut form);
+    let mut builder = client
+        .post(url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&form);
+    if let Some((client_id, client_secret)) = credentials {
+        builder = builder.basic_auth(client_id, Some(client_secret));
+    }
+
+    let response = builder
+        .send()
+        .await
+        .map_err(|e| FetchError::OAuth2(OAuth2Error::Request(e)))?;
+    let status = response.status();
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| FetchError::OAuth2(OAuth2Error::Request(e)))?;
+
+    if !status.is_success() {
+        return Err(FetchError::OAuth2(token_endpoint_error(status, &body)));
+    }
+    Ok(body)
+}
+
+/// Posts `form` to a token endpoint and parses the RFC 6749 token (or error) response
+async fn request_token(
+    client: &Client,
+    token_url: &str,
+    auth: &ClientAuthentication,
+    form: Vec<(&str, &str)>,
+) -> FetchResult<AccessToken> {
+    let body = post_form(client, token_url, auth, form).await?;
+    let token: TokenResponse = serde_json::from_slice(&body)
+        .map_err(|e| FetchError::OAuth2(OAuth2Error::InvalidTokenResponse(e)))?;
+    Ok(token.into())
+}
+
+fn token_endpoint_error(status: StatusCode, body: &[u8]) -> OAuth2Error {
+    match serde_json::from_slice::<TokenErrorResponse>(body) {
+        Ok(err) => OAuth2Error::TokenEndpoint {
+            status,
+            error: err.error,
+            description: err.error_description,
+        },
+        Err(_) => OAuth2Error::TokenEndpoint {
+            status,
+            error: String::from_utf8_lossy(body).into_owned(),
+            description: None,
+        },
+    }
+}
+
+/// OAuth2 client credentials grant (RFC 6749 section 4.4).
+///
+/// The access token is cached until shortly before it expires and is shared between every clone
+/// of this value, so concurrent requests wait on a single in-flight token request.
+///
+/// # Example
+/// ```rust
+/// use rust_fetch::{ClientCredentials, Fetch, FetchAuth, FetchConfig};
+///
+/// let credentials = ClientCredentials::new("https://auth.local/oauth/token", "my-client", "secret")
+///     .with_scopes(["orders:read", "orders:write"])
+///     .with_audience("https://api.local");
+///
+/// let client = Fetch::new(
+///     "https://api.local",
+///     Some(FetchConfig {
+///         auth: Some(FetchAuth::ClientCredentials(credentials)),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClientCredentials {
+    token_url: String,
+    client: ClientAuthentication,
+    scopes: Vec<String>,
+    audience: Option<String>,
+    expiry_margin: Duration,
+    token: Arc<Mutex<Option<AccessToken>>>,
+}
+
+impl ClientCredentials {
+    /// Creates a new client credentials grant against `token_url`
+    pub fn new(token_url: &str, client_id: &str, client_secret: &str) -> Self {
+        Self {
+            token_url: token_url.to_string(),
+            client: ClientAuthentication {
+                client_id: client_id.to_string(),
+                client_secret: Some(client_secret.to_string()),
+                method: Default::default(),
+            },
+            scopes: Vec::new(),
+            audience: None,
+            expiry_margin: Duration::from_secs(30),
+            token: Default::default(),
+        }
+    }
+
+    /// Sets how the client id and secret are sent to the token endpoint (defaults to Basic auth)
+    pub fn with_auth_method(mut self, auth_method: ClientAuthMethod) -> Self {
+        self.client.method = auth_method;
+        self
+    }
+
+    /// Who the tokens are issued to: the client, for the requested scopes and audience
+    pub(crate) fn identity(&self) -> String {
+        format!(
+            "client-credentials {} {} {} {}",
+            self.token_url,
+            self.client.client_id,
+            self.scopes.join(" "),
+            self.audience.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// Sets the scopes to request. They are sent space separated in the `scope` field
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the `audience` field sent to the token endpoint
+    pub fn with_audience(mut self, audience: &str) -> Self {
+        self.audience = Some(audience.to_string());
+        self
+    }
+
+    /// How long before `expires_in` the token is considered expired and refreshed (defaults to 30s)
+    pub fn with_expiry_margin(mut self, margin: Duration) -> Self {
+        self.expiry_margin = margin;
+        self
+    }
+
+    /// Returns a valid access token, requesting a new one from the token endpoint if needed
+    pub async fn access_token(&self, client: &Client) -> FetchResult<AccessToken> {
+        let mut token = self.token.lock().await;
+        if let Some(current) = token.as_ref() {
+            if !current.expires_within(self.expiry_margin) {
+                return Ok(current.clone());
+            }
+        }
+
+        let scope = self.scopes.join(" ");
+        let mut form: Vec<(&str, &str)> = vec![("grant_type", "client_credentials")];
+        if !scope.is_empty() {
+            form.push(("scope", &scope));
+        }
+        if let Some(audience) = &self.audience {
+            form.push(("audience", audience));
+        }
+
+        let new_token = request_token(client, &self.token_url, &self.client, form).await?;
+        *token = Some(new_token.clone());
+        Ok(new_token)
+    }
+
+    /// Drops the cached token if it is still `access_token`, so the next request fetches a new one
+    pub async fn invalidate(&self, access_token: &str) {
+        let mut token = self.token.lock().await;
+        if token
+            .as_ref()
+            .is_some_and(|current| current.access_token == access_token)
+        {
+            *token = None;
+        }
+    }
+}
+
+/// OAuth2 refresh token grant (RFC 6749 section 6).
+///
+/// Keeps the current token in a `TokenStore` and exchanges the refresh token for a new access
+/// token shortly before it expires. If the server rotates the refresh token, the new one is saved;
+/// otherwise the previous refresh token is kept.
+#[derive(Clone)]
+pub struct RefreshTokenGrant {
+    token_url: String,
+    client: ClientAuthentication,
+    scopes: Vec<String>,
+    expiry_margin: Duration,
+    store: Arc<dyn TokenStore>,
+    refresh_lock: Arc<Mutex<()>>,
+    /// Tells grants apart, as each store can hold the tokens of another user
+    id: String,
+}
+
+impl Debug for RefreshTokenGrant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RefreshTokenGrant")
+            .field("token_url", &self.token_url)
+            .field("client", &self.client)
+            .field("scopes", &self.scopes)
+            .field("expiry_margin", &self.expiry_margin)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RefreshTokenGrant {
+    /// Creates a refresh token grant for a public client. Tokens are read from and written to `store`
+    pub fn new(token_url: &str, client_id: &str, store: Arc<dyn TokenStore>) -> Self {
+        Self {
+            token_url: token_url.to_string(),
+            client: ClientAuthentication {
+                client_id: client_id.to_string(),
+                client_secret: None,
+                method: Default::default(),
+            },
+            scopes: Vec::new(),
+            expiry_margin: Duration::from_secs(30),
+            store,
+            refresh_lock: Default::default(),
+            id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    /// Authenticates a confidential client with `client_secret`, sent using `auth_method`
+    pub fn with_client_secret(mut self, client_secret: &str, auth_method: ClientAuthMethod) -> Self {
+        self.client.client_secret = Some(client_secret.to_string());
+        self.client.method = auth_method;
+        self
+    }
+
+    /// The user the grant's tokens belong to. Clones share it
+    pub(crate) fn identity(&self) -> String {
+        format!("refresh-token {}", self.id)
+    }
+
+    /// Narrows the scopes requested when refreshing
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// How long before `expires_in` the token is considered expired and refreshed (defaults to 30s)
+    pub fn with_expiry_margin(mut self, margin: Duration) -> Self {
+        self.expiry_margin = margin;
+        self
+    }
+
+    /// Returns a valid access token, refreshing it if it is about to expire
+    pub async fn access_token(&self, client: &Client) -> FetchResult<AccessToken> {
+        let _guard = self.refresh_lock.lock().await;
+        let current = self
+            .store
+            .load()
+            .ok_or(FetchError::OAuth2(OAuth2Error::NoRefreshToken))?;
+        if !current.expires_within(self.expiry_margin) {
+            return Ok(current);
+        }
+        self.refresh(client, current).await
+    }
+
+    /// Marks the stored token as expired if it is still `access_token`, forcing a refresh
+    pub async fn invalidate(&self, access_token: &str) {
+        let _guard = self.refresh_lock.lock().await;
+        if let Some(mut current) = self.store.load() {
+            if current.access_token == access_token {
+                current.expires_at = Some(SystemTime::UNIX_EPOCH);
+                self.store.save(&current);
+            }
+        }
+    }
+
+    async fn refresh(&self, client: &Client, current: AccessToken) -> FetchResult<AccessToken> {
+        let refresh_token = current
+            .refresh_token
+            .ok_or(FetchError::OAuth2(OAuth2Error::NoRefreshToken))?;
+        let scope = self.scopes.join(" ");
+        let mut form: Vec<(&str, &str)> = vec![
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+        ];
+        if !scope.is_empty() {
+            form.push(("scope", &scope));
+        }
+
+        let mut new_token = request_token(client, &self.token_url, &self.client, form).await?;
+        if new_token.refresh_token.is_none() {
+            new_token.refresh_token = Some(refresh_token.clone());
+        }
+        self.store.save(&new_token);
+        Ok(new_token)
+    }
+}
+
+/// The response of a device authorization endpoint (RFC 8628 section 3.2).
+///
+/// Show `user_code` and `verification_uri` to the user, then call `DeviceAuthorization::poll`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    /// Minimum number of seconds between polling requests
+    #[serde(default = "default_poll_interval")]
+    pub interval: u64,
+}
+
+fn default_poll_interval() -> u64 {
+    5
+}
+
+/// OAuth2 device authorization grant (RFC 8628) for input-constrained clients such as CLIs.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use rust_fetch::{DeviceAuthorization, Fetch, FetchAuth, FetchConfig, MemoryTokenStore};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let http = rust_fetch::reqwest::Client::new();
+///     let device = DeviceAuthorization::new(
+///         "https://auth.local/device/code",
+///         "https://auth.local/token",
+///         "my-cli",
+///     )
+///     .with_scopes(["offline_access"]);
+///
+///     let code = device.start(&http).await.unwrap();
+///     println!("Open {} and enter {}", code.verification_uri, code.user_code);
+///     let token = device.poll(&http, &code).await.unwrap();
+///
+///     let store = Arc::new(MemoryTokenStore::new(Some(token)));
+///     let client = Fetch::new(
+///         "https://api.local",
+///         Some(FetchConfig {
+///             auth: Some(FetchAuth::RefreshToken(device.refresh_grant(store))),
+///             ..Default::default()
+///         }),
+///     )
+///     .unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DeviceAuthorization {
+    device_authorization_url: String,
+    token_url: String,
+    client: ClientAuthentication,
+    scopes: Vec<String>,
+}
+
+impl DeviceAuthorization {
+    pub fn new(device_authorization_url: &str, token_url: &str, client_id: &str) -> Self {
+        Self {
+            device_authorization_url: device_authorization_url.to_string(),
+            token_url: token_url.to_string(),
+            client: ClientAuthentication {
+                client_id: client_id.to_string(),
+                client_secret: None,
+                method: Default::default(),
+            },
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Authenticates a confidential client with `client_secret`, sent using `auth_method`
+    pub fn with_client_secret(mut self, client_secret: &str, auth_method: ClientAuthMethod) -> Self {
+        self.client.client_secret = Some(client_secret.to_string());
+        self.client.method = auth_method;
+        self
+    }
+
+    /// Sets the scopes to request. They are sent space separated in the `scope` field
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Starts the flow by requesting a device and user code
+    pub async fn start(&self, client: &Client) -> FetchResult<DeviceCode> {
+        let scope = self.scopes.join(" ");
+        let mut form: Vec<(&str, &str)> = Vec::new();
+        if !scope.is_empty() {
+            form.push(("scope", &scope));
+        }
+
+        let body = post_form(client, &self.device_authorization_url, &self.client, form).await?;
+        serde_json::from_slice(&body)
+            .map_err(|e| FetchError::OAuth2(OAuth2Error::InvalidTokenResponse(e)))
+    }
+
+    /// Polls the token endpoint until the user approves or denies the request, or the code expires.
+    ///
+    /// `authorization_pending` keeps polling and `slow_down` increases the interval by 5 seconds
+    pub async fn poll(&self, client: &Client, code: &DeviceCode) -> FetchResult<AccessToken> {
+        let deadline = Instant::now() + Duration::from_secs(code.expires_in);
+        let mut interval = Duration::from_secs(code.interval);
+
+        loop {
+            tokio::time::sleep(interval).await;
+            if Instant::now() >= deadline {
+                return Err(FetchError::OAuth2(OAuth2Error::DeviceCodeExpired));
+            }
+
+            let form = vec![
+                ("grant_type", DEVICE_CODE_GRANT),
+                ("device_code", code.device_code.as_str()),
+            ];
+            match request_token(client, &self.token_url, &self.client, form).await {
+                Ok(token) => return Ok(token),
+                Err(FetchError::OAuth2(OAuth2Error::TokenEndpoint { error, .. }))
+                    if error == "authorization_pending" => {}
+                Err(FetchError::OAuth2(OAuth2Error::TokenEndpoint { error, .. }))
+                    if error == "slow_down" =>
+                {
+                    interval = slow_down(interval);
+                }
+                Err(FetchError::OAuth2(OAuth2Error::TokenEndpoint { error, .. }))
+                    if error == "expired_token" =>
+                {
+                    return Err(FetchError::OAuth2(OAuth2Error::DeviceCodeExpired));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Creates a `RefreshTokenGrant` for the same client and token endpoint, backed by `store`
+    pub fn refresh_grant(&self, store: Arc<dyn TokenStore>) -> RefreshTokenGrant {
+        RefreshTokenGrant {
+            client: self.client.clone(),
+            ..RefreshTokenGrant::new(&self.token_url, &self.client.client_id, store)
+        }
+    }
+}
+
+fn slow_down(interval: Duration) -> Duration {
+    interval + Duration::from_secs(5)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{slow_down, AccessToken};
+
+    fn token(expires_at: Option<SystemTime>) -> AccessToken {
+        AccessToken {
+            access_token: "token".to_string(),
+            token_type: "Bearer".to_string(),
+            refresh_token: None,
+            scope: None,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_token_without_expiry_never_expires() {
+        assert!(!token(None).expires_within(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn test_token_expires_within_margin() {
+        let token = token(Some(SystemTime::now() + Duration::from_secs(10)));
+        assert!(token.expires_within(Duration::from_secs(30)));
+        assert!(!token.expires_within(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_slow_down_adds_five_seconds() {
+        assert_eq!(Duration::from_secs(10), slow_down(Duration::from_secs(5)));
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::Request;
+use tokio::time::Instant;
+
+use crate::{
+    error::{FetchError, FetchResult},
+    utils::glob_match,
+};
+
+/// Which requests share a rate limit budget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// All requests share one budget
+    Global,
+    /// Every host gets its own budget
+    Host,
+    /// Requests whose path matches one of the patterns (`*` matches anything) get a budget per
+    /// pattern. Requests matching none of them are not limited
+    Endpoint(Vec<String>),
+}
+
+/// How often budgets whose requests all lie in the past are forgotten
+const PRUNE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What happens to a request once the budget is exhausted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitMode {
+    /// Wait until the request fits in the budget
+    Wait,
+    /// Fail immediately with `FetchError::RateLimited`
+    FailFast,
+}
+
+/// A client-side rate limiter using the generic cell rate algorithm (GCRA), which behaves like a
+/// token bucket refilled at `requests` per `per` holding up to `burst` tokens.
+///
+/// Clones share their budgets.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rust_fetch::{Fetch, FetchConfig, RateLimitKey, RateLimitMode, RateLimiter};
+///
+/// let client = Fetch::new(
+///     "https://api.local",
+///     Some(FetchConfig {
+///         rate_limiter: Some(
+///             RateLimiter::new(10, Duration::from_secs(1))
+///                 .with_key(RateLimitKey::Endpoint(vec!["/search*".to_string()]))
+///                 .with_mode(RateLimitMode::FailFast),
+///         ),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    /// Time between two requests at the sustained rate
+    interval: Duration,
+    burst: u32,
+    key: RateLimitKey,
+    mode: RateLimitMode,
+    arrivals: Arc<Mutex<Arrivals>>,
+}
+
+#[derive(Debug, Default)]
+struct Arrivals {
+    /// Theoretical arrival time of the next request, per budget
+    next: HashMap<String, Instant>,
+    pruned_at: Option<Instant>,
+}
+
+impl Arrivals {
+    /// Forgets budgets whose next request may be sent right away, which is the same as not
+    /// having sent any, so per host budgets do not pile up
+    fn prune(&mut self, now: Instant) {
+        let due = self
+            .pruned_at
+            .is_none_or(|pruned_at| now.saturating_duration_since(pruned_at) >= PRUNE_INTERVAL);
+        if due {
+            self.next.retain(|_, arrival| *arrival > now);
+            self.pruned_at = Some(now);
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Allows `requests` per `per`, all at once if needed. Waits by default when the budget is
+    /// exhausted
+    pub fn new(requests: u32, per: Duration) -> Self {
+        let requests = requests.max(1);
+        Self {
+            interval: per / requests,
+            burst: requests,
+            key: RateLimitKey::Global,
+            mode: RateLimitMode::Wait,
+            arrivals: Default::default(),
+        }
+    }
+
+    /// The number of requests that may be sent back to back
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    pub fn with_key(mut self, key: RateLimitKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: RateLimitMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    fn budget(&self, request: &Request) -> Option<String> {
+        match &self.key {
+            RateLimitKey::Global => Some("global".to_string()),
+            RateLimitKey::Host => Some(request.url().host_str().unwrap_or_default().to_string()),
+            RateLimitKey::Endpoint(patterns) => patterns
+                .iter()
+                .find(|pattern| glob_match(pattern, request.url().path()))
+                .cloned(),
+        }
+    }
+
+    /// Reserves a slot for a request at `now`. Returns how long it has to wait for it, or the
+    /// time until a slot frees up if the limiter fails fast
+    fn reserve(&self, budget: &str, now: Instant) -> Result<Duration, Duration> {
+        self.reserve_in(budget, now, self.mode)
+    }
+
+    fn reserve_in(
+        &self,
+        budget: &str,
+        now: Instant,
+        mode: RateLimitMode,
+    ) -> Result<Duration, Duration> {
+        let tolerance = self
+            .interval
+            .checked_mul(self.burst - 1)
+            .unwrap_or(Duration::MAX);
+        let mut arrivals = self.arrivals.lock().unwrap();
+        arrivals.prune(now);
+        let arrival = arrivals.next.get(budget).copied().unwrap_or(now).max(now);
+
+        let allowed_at = arrival.checked_sub(tolerance).unwrap_or(now).max(now);
+        let wait = allowed_at - now;
+        if !wait.is_zero() && mode == RateLimitMode::FailFast {
+            return Err(wait);
+        }
+        arrivals
+            .next
+            .insert(budget.to_string(), arrival + self.interval);
+        Ok(wait)
+    }
+
+    /// Waits for (or fails without) a slot in the budget of `request`
+    pub(crate) async fn acquire(&self, request: &Request) -> FetchResult<()> {
+        let Some(budget) = self.budget(request) else {
+            return Ok(());
+        };
+        match self.reserve(&budget, Instant::now()) {
+            Ok(wait) if wait.is_zero() => Ok(()),
+            Ok(wait) => {
+                tokio::time::sleep(wait).await;
+                Ok(())
+            }
+            Err(retry_after) => Err(FetchError::RateLimited {
+                key: budget,
+                retry_after,
+            }),
+        }
+    }
+
+    /// Takes a slot in the budget of `request` if one is free right now, never waiting for one
+    pub(crate) fn try_acquire(&self, request: &Request) -> bool {
+        match self.budget(request) {
+            Some(budget) => self
+                .reserve_in(&budget, Instant::now(), RateLimitMode::FailFast)
+                .is_ok(),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::{RateLimitMode, RateLimiter};
+
+    #[test]
+    fn test_burst_then_sustained_rate() {
+        let limiter = RateLimiter::new(10, Duration::from_secs(1)).with_burst(2);
+        let now = Instant::now();
+
+        assert_eq!(Ok(Duration::ZERO), limiter.reserve("", now));
+        assert_eq!(Ok(Duration::ZERO), limiter.reserve("", now));
+        assert_eq!(Ok(Duration::from_millis(100)), limiter.reserve("", now));
+        assert_eq!(Ok(Duration::from_millis(200)), limiter.reserve("", now));
+        // The budget refills over time
+        assert_eq!(
+            Ok(Duration::ZERO),
+            limiter.reserve("", now + Duration::from_millis(400))
+        );
+    }
+
+    #[test]
+    fn test_fail_fast_does_not_consume_budget() {
+        let limiter =
+            RateLimiter::new(1, Duration::from_secs(1)).with_mode(RateLimitMode::FailFast);
+        let now = Instant::now();
+
+        assert_eq!(Ok(Duration::ZERO), limiter.reserve("a", now));
+        assert_eq!(Err(Duration::from_secs(1)), limiter.reserve("a", now));
+        assert_eq!(
+            Err(Duration::from_millis(500)),
+            limiter.reserve("a", now + Duration::from_millis(500))
+        );
+        assert_eq!(Ok(Duration::ZERO), limiter.reserve("b", now));
+    }
+
+    #[test]
+    fn test_idle_budgets_are_forgotten() {
+        let limiter = RateLimiter::new(10, Duration::from_secs(1));
+        let now = Instant::now();
+        for host in 0..100 {
+            limiter.reserve(&format!("host-{host}"), now).unwrap();
+        }
+        assert_eq!(100, limiter.arrivals.lock().unwrap().next.len());
+
+        let later = now + Duration::from_secs(2);
+        assert_eq!(Ok(Duration::ZERO), limiter.reserve("host-0", later));
+        assert_eq!(1, limiter.arrivals.lock().unwrap().next.len());
+    }
+
+    #[test]
+    fn test_huge_burst_does_not_overflow() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(u64::MAX / 8)).with_burst(u32::MAX);
+        let now = Instant::now();
+        assert_eq!(Ok(Duration::ZERO), limiter.reserve("", now));
+        assert_eq!(Ok(Duration::ZERO), limiter.reserve("", now));
+    }
+}
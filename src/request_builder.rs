@@ -0,0 +1,193 @@
+use std::{collections::HashMap, time::Duration};
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::FetchResult, utils::millis_rounded_up, CancellationToken, ContentType, Fetch,
+    FetchOptions, FetchResponse, IdempotencyKey,
+};
+
+/// Builds a single call step by step, see `Fetch::request` and `Fetch::get_request`.
+///
+/// Every setting maps onto a `FetchOptions` field, so calls made this way go through exactly the
+/// same pipeline as `Fetch::post`, `Fetch::get` and friends.
+///
+/// # Example
+/// ```rust
+/// use httpmock::prelude::*;
+/// use rust_fetch::Fetch;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize)]
+/// struct NewOrder {
+///     sku: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Order {
+///     id: u32,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let server = MockServer::start();
+///     server.mock(|when, then| {
+///         when.method(POST)
+///             .path("/orders")
+///             .query_param("dry_run", "true")
+///             .header("x-tenant", "acme");
+///         then.status(201).json_body(serde_json::json!({ "id": 7 }));
+///     });
+///
+///     let fetch = Fetch::new(&server.base_url(), None).unwrap();
+///     let order = fetch
+///         .post_request("/orders")
+///         .query("dry_run", "true")
+///         .header("x-tenant", "acme")
+///         .json(&NewOrder { sku: "pen".to_string() })
+///         .timeout(std::time::Duration::from_secs(5))
+///         .send::<Order>()
+///         .await
+///         .unwrap();
+///     assert_eq!(7, order.body.unwrap().id);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FetchRequestBuilder<'a> {
+    fetch: &'a Fetch,
+    method: Method,
+    endpoint: String,
+    options: FetchOptions,
+    body: Option<FetchResult<(Vec<u8>, ContentType)>>,
+}
+
+impl<'a> FetchRequestBuilder<'a> {
+    pub(crate) fn new(fetch: &'a Fetch, method: Method, endpoint: &str) -> Self {
+        Self {
+            fetch,
+            method,
+            endpoint: endpoint.to_string(),
+            options: Default::default(),
+            body: None,
+        }
+    }
+
+    /// Replaces all settings made so far with `options`
+    pub fn options(mut self, options: FetchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Adds a query parameter
+    pub fn query(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.options
+            .params
+            .get_or_insert_with(HashMap::new)
+            .insert(key.into(), value.to_string());
+        self
+    }
+
+    /// Adds a header, on top of the default headers of the client
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options
+            .headers
+            .get_or_insert_with(HashMap::new)
+            .insert(key.into(), value.into());
+        self
+    }
+
+    /// Sends `body` serialized with the content type set via `content_type`, or the client's
+    /// default content type
+    pub fn body<U: Serialize + ?Sized>(mut self, body: &U) -> Self {
+        self.body = Some(self.fetch.make_body(body, Some(&self.options)));
+        self
+    }
+
+    /// Sends `body` as JSON
+    pub fn json<U: Serialize + ?Sized>(mut self, body: &U) -> Self {
+        self.options.content_type = Some(ContentType::Json);
+        self.body(body)
+    }
+
+    /// Sends `body` as `application/x-www-form-urlencoded`
+    pub fn form<U: Serialize + ?Sized>(mut self, body: &U) -> Self {
+        self.options.content_type = Some(ContentType::UrlEncoded);
+        self.body(body)
+    }
+
+    /// The content type to serialize the body set afterwards with `body` in
+    pub fn content_type(mut self, content_type: ContentType) -> Self {
+        self.options.content_type = Some(content_type);
+        self
+    }
+
+    pub fn accept(mut self, accept: ContentType) -> Self {
+        self.options.accept = Some(accept);
+        self
+    }
+
+    /// How long a single attempt may take. Sub-millisecond parts are rounded up
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout_ms = Some(millis_rounded_up(timeout));
+        self
+    }
+
+    /// How long the whole call may take, including retries. Sub-millisecond parts are rounded up
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.options.deadline_ms = Some(millis_rounded_up(deadline));
+        self
+    }
+
+    pub fn idempotency_key(mut self, key: IdempotencyKey) -> Self {
+        self.options.idempotency_key = Some(key);
+        self
+    }
+
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.options.cancellation_token = Some(token);
+        self
+    }
+
+    /// Whether the response body is deserialized into `T` (the default) or only kept raw
+    pub fn deserialize_body(mut self, deserialize_body: bool) -> Self {
+        self.options.deserialize_body = deserialize_body;
+        self
+    }
+
+    /// Sends the request, deserializing the response body into `T`
+    pub async fn send<T>(self) -> FetchResult<FetchResponse<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let body = self.body.transpose()?;
+        self.fetch
+            .execute_body(self.method, &self.endpoint, body, Some(self.options))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Method;
+
+    use crate::{ContentType, Fetch};
+
+    #[test]
+    fn test_settings_map_onto_options() {
+        let fetch = Fetch::new("http://localhost", None).unwrap();
+        let builder = fetch
+            .request(Method::GET, "/orders")
+            .query("page", 2)
+            .header("x-tenant", "acme")
+            .accept(ContentType::TextXml)
+            .deserialize_body(false);
+
+        let options = &builder.options;
+        assert_eq!("2", options.params.as_ref().unwrap()["page"]);
+        assert_eq!("acme", options.headers.as_ref().unwrap()["x-tenant"]);
+        assert!(matches!(options.accept, Some(ContentType::TextXml)));
+        assert!(!options.deserialize_body);
+        assert!(builder.body.is_none());
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rand::Rng;
+use reqwest::{Request, Response, StatusCode};
+
+/// A budget shared by all calls that caps retries at a fraction of the successful traffic, so an
+/// outage does not multiply the load on the failing upstream.
+///
+/// Every successful request adds `ratio` tokens (up to `max_tokens`) and every retry costs one.
+/// Once the budget is empty, failed calls are not retried and return
+/// `FetchError::RetryBudgetExhausted`.
+///
+/// Clones share their tokens.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    ratio: f64,
+    max_tokens: f64,
+    tokens: Arc<Mutex<f64>>,
+}
+
+impl RetryBudget {
+    /// Allows retries for up to `ratio` (between 0 and 1) of the successful requests, with up to
+    /// 10 retries saved up
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            ratio: ratio.clamp(0.0, 1.0),
+            max_tokens: 10.0,
+            tokens: Arc::new(Mutex::new(10.0)),
+        }
+    }
+
+    /// How many unused retries can be saved up for a burst of failures
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens as f64;
+        *self.tokens.lock().unwrap() = self.max_tokens;
+        self
+    }
+
+    /// The number of retries currently available
+    pub fn available(&self) -> u32 {
+        *self.tokens.lock().unwrap() as u32
+    }
+
+    pub(crate) fn deposit(&self) {
+        let mut tokens = self.tokens.lock().unwrap();
+        *tokens = (*tokens + self.ratio).min(self.max_tokens);
+    }
+
+    pub(crate) fn try_withdraw(&self) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Retries requests that failed to connect, timed out or were answered with `429`, `502`, `503` or
+/// `504`.
+///
+/// Only idempotent methods and requests carrying an `Idempotency-Key` are retried. Attempts are
+/// spaced with exponential backoff and full jitter, but never sooner than a `Retry-After` asks.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use rust_fetch::{Fetch, FetchConfig, RetryBudget, RetryPolicy};
+///
+/// let client = Fetch::new(
+///     "https://ledger.local",
+///     Some(FetchConfig {
+///         retry: Some(
+///             RetryPolicy::new(3)
+///                 .with_backoff(Duration::from_millis(50), Duration::from_secs(2))
+///                 .with_budget(RetryBudget::new(0.1)),
+///         ),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    pub(crate) budget: Option<RetryBudget>,
+}
+
+impl RetryPolicy {
+    /// Retries a call up to `max_retries` times, starting with a 100ms backoff capped at 5 seconds
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            budget: None,
+        }
+    }
+
+    pub fn with_backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay.max(base_delay);
+        self
+    }
+
+    pub fn with_budget(mut self, budget: RetryBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    pub(crate) fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    pub(crate) fn applies_to(request: &Request) -> bool {
+        request.method().is_idempotent() || request.headers().contains_key(crate::IDEMPOTENCY_KEY)
+    }
+
+    pub(crate) fn is_retryable(response: &Response) -> bool {
+        matches!(
+            response.status(),
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    /// The delay before retry number `retry` (starting at 0), or `None` if the server asked to
+    /// wait longer than the maximum backoff
+    pub(crate) fn backoff(&self, retry: u32, response: Option<&Response>) -> Option<Duration> {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let jittered = ceiling.mul_f64(rand::thread_rng().gen_range(0.0..=1.0));
+
+        let retry_after = response
+            .and_then(|response| response.headers().get(reqwest::header::RETRY_AFTER))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or_default();
+        if retry_after > self.max_delay {
+            return None;
+        }
+        Some(jittered.max(retry_after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{RetryBudget, RetryPolicy};
+
+    #[test]
+    fn test_budget_is_refilled_by_successes() {
+        let budget = RetryBudget::new(0.5).with_max_tokens(1);
+
+        assert!(budget.try_withdraw());
+        assert!(!budget.try_withdraw());
+        budget.deposit();
+        budget.deposit();
+        budget.deposit();
+        assert_eq!(1, budget.available());
+        assert!(budget.try_withdraw());
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::new(10)
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(300));
+        for retry in 0..10 {
+            assert!(policy.backoff(retry, None).unwrap() <= Duration::from_millis(300));
+        }
+    }
+}
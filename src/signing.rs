@@ -0,0 +1,15 @@
+use std::fmt::Debug;
+
+use reqwest::Request;
+
+use crate::error::FetchResult;
+
+/// Signs a fully built request right before it is sent.
+///
+/// Signers see the final URL, headers and body produced by `Fetch`, and run again for every
+/// attempt (e.g. when a request is re-sent after a `401`), so timestamps are always fresh.
+/// Default headers (`FetchConfig::headers`) are already part of the request and can be covered by
+/// the signature.
+pub trait RequestSigner: Debug + Send + Sync {
+    fn sign(&self, request: &mut Request) -> FetchResult<()>;
+}
@@ -0,0 +1,396 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{header::HeaderMap, StatusCode, Url};
+use tokio::time::Instant;
+
+use crate::{
+    error::{FetchError, FetchResult},
+    utils::parse_params,
+};
+
+/// The quota a server advertised for a host in its rate limit headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quota {
+    /// The number of requests allowed per window, if advertised
+    pub limit: Option<u64>,
+    /// The requests left in the current window, minus the ones sent since it was advertised
+    pub remaining: u64,
+    /// When the window resets
+    pub reset_at: Instant,
+}
+
+#[derive(Debug)]
+struct HostState {
+    quota: Quota,
+    /// When the next request may be sent while pacing
+    next_send: Instant,
+}
+
+/// Slows requests down based on the quota servers advertise, so the client stays within it
+/// instead of running into `429 Too Many Requests`.
+///
+/// Understands `X-RateLimit-Limit` / `X-RateLimit-Remaining` / `X-RateLimit-Reset`, the IETF
+/// `RateLimit-Limit` / `RateLimit-Remaining` / `RateLimit-Reset` and `RateLimit` /
+/// `RateLimit-Policy` fields, and `Retry-After` on `429` and `503` responses. Once fewer than
+/// `threshold` of the quota is left, the remaining requests are spread evenly until the window
+/// resets; when nothing is left, requests wait for the reset.
+///
+/// Clones share their state, which can be inspected with `quota`.
+///
+/// # Example
+/// ```rust
+/// use rust_fetch::{AdaptiveThrottle, Fetch, FetchConfig};
+///
+/// let throttle = AdaptiveThrottle::new();
+/// let client = Fetch::new(
+///     "https://api.github.local",
+///     Some(FetchConfig {
+///         throttle: Some(throttle.clone()),
+///         ..Default::default()
+///     }),
+/// );
+/// assert!(client.is_ok());
+/// assert!(throttle.quota("api.github.local").is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptiveThrottle {
+    threshold: f64,
+    max_wait: Duration,
+    hosts: Arc<Mutex<HashMap<String, HostState>>>,
+}
+
+impl Default for AdaptiveThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdaptiveThrottle {
+    /// Starts pacing below 10% of the quota, and waits at most a minute for a request
+    pub fn new() -> Self {
+        Self {
+            threshold: 0.1,
+            max_wait: Duration::from_secs(60),
+            hosts: Default::default(),
+        }
+    }
+
+    /// The fraction of the quota below which requests are paced. When the server does not
+    /// advertise a limit, pacing starts once `remaining` is at most 1
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Requests that would have to wait longer fail with `FetchError::RateLimited` instead
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    /// The last known quota of `host`, or `None` if it is unknown or its window has reset
+    pub fn quota(&self, host: &str) -> Option<Quota> {
+        let hosts = self.hosts.lock().unwrap();
+        hosts
+            .get(host)
+            .filter(|state| state.quota.reset_at > Instant::now())
+            .map(|state| state.quota.clone())
+    }
+
+    /// The known quotas of all hosts whose window has not reset yet
+    pub fn quotas(&self) -> HashMap<String, Quota> {
+        let now = Instant::now();
+        let hosts = self.hosts.lock().unwrap();
+        hosts
+            .iter()
+            .filter(|(_, state)| state.quota.reset_at > now)
+            .map(|(host, state)| (host.clone(), state.quota.clone()))
+            .collect()
+    }
+
+    fn is_low(&self, quota: &Quota) -> bool {
+        match quota.limit {
+            Some(limit) if limit > 0 => (quota.remaining as f64) < limit as f64 * self.threshold,
+            _ => quota.remaining <= 1,
+        }
+    }
+
+    /// Reserves a request to `host` at `now`, returning how long it has to wait
+    fn reserve(&self, host: &str, now: Instant) -> FetchResult<Duration> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let Some(state) = hosts.get_mut(host) else {
+            return Ok(Duration::ZERO);
+        };
+        if state.quota.reset_at <= now {
+            hosts.remove(host);
+            return Ok(Duration::ZERO);
+        }
+
+        let wait = if state.quota.remaining == 0 {
+            state.quota.reset_at - now
+        } else if self.is_low(&state.quota) {
+            let send_at = state.next_send.max(now).min(state.quota.reset_at);
+            let remaining = u32::try_from(state.quota.remaining).unwrap_or(u32::MAX);
+            let interval = (state.quota.reset_at - send_at) / remaining.saturating_add(1);
+            state.next_send = send_at + interval;
+            send_at - now
+        } else {
+            Duration::ZERO
+        };
+
+        if wait > self.max_wait {
+            return Err(FetchError::RateLimited {
+                key: host.to_string(),
+                retry_after: wait,
+            });
+        }
+        state.quota.remaining = state.quota.remaining.saturating_sub(1);
+        Ok(wait)
+    }
+
+    pub(crate) async fn acquire(&self, url: &Url) -> FetchResult<()> {
+        let wait = self.reserve(url.host_str().unwrap_or_default(), Instant::now())?;
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Records the quota advertised in a response from `url`
+    pub(crate) fn observe(&self, url: &Url, status: StatusCode, headers: &HeaderMap) {
+        let now = Instant::now();
+        let Some(quota) = parse_quota(status, headers, now) else {
+            return;
+        };
+        let mut hosts = self.hosts.lock().unwrap();
+        let host = url.host_str().unwrap_or_default().to_string();
+        let next_send = hosts.get(&host).map(|state| state.next_send).unwrap_or(now);
+        hosts.insert(host, HostState { quota, next_send });
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+}
+
+fn number(value: Option<&str>) -> Option<u64> {
+    value?.trim().trim_matches('"').parse().ok()
+}
+
+/// `X-RateLimit-Reset` is either seconds until the reset or a unix timestamp
+fn reset_delay(seconds: u64) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if seconds > 1_000_000_000 {
+        Duration::from_secs(seconds.saturating_sub(now))
+    } else {
+        Duration::from_secs(seconds)
+    }
+}
+
+/// Looks up `key` in the first item of a structured field like `"default";r=50;t=30`
+fn structured_param(value: &str, key: &str) -> Option<u64> {
+    let item = value.split(',').next()?;
+    item.split(';')
+        .skip(1)
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(name, _)| name.trim() == key)
+        .and_then(|(_, value)| number(Some(value)))
+}
+
+fn parse_quota(status: StatusCode, headers: &HeaderMap, now: Instant) -> Option<Quota> {
+    let mut limit = None;
+    let mut remaining = None;
+    let mut reset = None;
+
+    for prefix in ["x-ratelimit-", "ratelimit-"] {
+        limit = limit.or(number(header(headers, &format!("{prefix}limit"))));
+        remaining = remaining.or(number(header(headers, &format!("{prefix}remaining"))));
+        reset = reset.or(number(header(headers, &format!("{prefix}reset"))).map(reset_delay));
+    }
+
+    if let Some(value) = header(headers, "ratelimit") {
+        if value.contains(';') {
+            remaining = remaining.or(structured_param(value, "r"));
+            reset = reset.or(structured_param(value, "t").map(Duration::from_secs));
+        } else {
+            // Earlier drafts used `RateLimit: limit=100, remaining=50, reset=30`
+            let params = parse_params(value);
+            let param = |key: &str| {
+                params
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                    .and_then(|(_, value)| value.parse().ok())
+            };
+            limit = limit.or(param("limit"));
+            remaining = remaining.or(param("remaining"));
+            reset = reset.or(param("reset").map(Duration::from_secs));
+        }
+    }
+    if let Some(value) = header(headers, "ratelimit-policy") {
+        limit = limit.or(structured_param(value, "q"));
+        if reset.is_none() {
+            reset = structured_param(value, "w").map(Duration::from_secs);
+        }
+    }
+
+    if matches!(status.as_u16(), 429 | 503) {
+        if let Some(retry_after) = header(headers, "retry-after").and_then(|value| {
+            number(Some(value)).map(Duration::from_secs).or_else(|| {
+                httpdate::parse_http_date(value)
+                    .ok()?
+                    .duration_since(SystemTime::now())
+                    .ok()
+            })
+        }) {
+            remaining = Some(0);
+            reset = Some(reset.map_or(retry_after, |reset| reset.max(retry_after)));
+        }
+    }
+
+    Some(Quota {
+        limit,
+        remaining: remaining?,
+        reset_at: now + reset?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::{
+        header::{HeaderMap, HeaderName, HeaderValue},
+        StatusCode,
+    };
+    use tokio::time::Instant;
+
+    use super::{parse_quota, AdaptiveThrottle, HostState, Quota};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    HeaderName::from_static(name),
+                    HeaderValue::from_static(value),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_x_ratelimit_headers() {
+        let now = Instant::now();
+        let quota = parse_quota(
+            StatusCode::OK,
+            &headers(&[
+                ("x-ratelimit-limit", "100"),
+                ("x-ratelimit-remaining", "42"),
+                ("x-ratelimit-reset", "30"),
+            ]),
+            now,
+        )
+        .unwrap();
+        assert_eq!(Some(100), quota.limit);
+        assert_eq!(42, quota.remaining);
+        assert_eq!(now + Duration::from_secs(30), quota.reset_at);
+    }
+
+    #[test]
+    fn test_parse_ietf_structured_fields() {
+        let now = Instant::now();
+        let quota = parse_quota(
+            StatusCode::OK,
+            &headers(&[
+                ("ratelimit", "\"default\";r=50;t=30"),
+                ("ratelimit-policy", "\"default\";q=100;w=60"),
+            ]),
+            now,
+        )
+        .unwrap();
+        assert_eq!(Some(100), quota.limit);
+        assert_eq!(50, quota.remaining);
+        assert_eq!(now + Duration::from_secs(30), quota.reset_at);
+    }
+
+    #[test]
+    fn test_parse_retry_after_on_429() {
+        let now = Instant::now();
+        let quota = parse_quota(
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers(&[("retry-after", "5")]),
+            now,
+        )
+        .unwrap();
+        assert_eq!(0, quota.remaining);
+        assert_eq!(now + Duration::from_secs(5), quota.reset_at);
+        assert!(parse_quota(StatusCode::OK, &headers(&[("retry-after", "5")]), now).is_none());
+    }
+
+    #[test]
+    fn test_paces_when_quota_is_low() {
+        let throttle = AdaptiveThrottle::new().with_threshold(0.5);
+        let now = Instant::now();
+        throttle.hosts.lock().unwrap().insert(
+            "api.local".to_string(),
+            HostState {
+                quota: Quota {
+                    limit: Some(10),
+                    remaining: 3,
+                    reset_at: now + Duration::from_secs(4),
+                },
+                next_send: now,
+            },
+        );
+
+        assert_eq!(Duration::ZERO, throttle.reserve("api.local", now).unwrap());
+        assert_eq!(
+            Duration::from_secs(1),
+            throttle.reserve("api.local", now).unwrap()
+        );
+        assert_eq!(
+            Duration::from_secs(2),
+            throttle.reserve("api.local", now).unwrap()
+        );
+        // The quota is used up, so the next request waits for the reset
+        assert_eq!(
+            Duration::from_secs(4),
+            throttle.reserve("api.local", now).unwrap()
+        );
+        assert_eq!(
+            Duration::ZERO,
+            throttle.reserve("other.local", now).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_paces_huge_quotas_without_overflowing() {
+        let throttle = AdaptiveThrottle::new().with_threshold(0.5);
+        let now = Instant::now();
+        for remaining in [u64::from(u32::MAX), u64::MAX / 4] {
+            throttle.hosts.lock().unwrap().insert(
+                "api.local".to_string(),
+                HostState {
+                    quota: Quota {
+                        limit: Some(u64::MAX),
+                        remaining,
+                        reset_at: now + Duration::from_secs(4),
+                    },
+                    next_send: now,
+                },
+            );
+            assert_eq!(Duration::ZERO, throttle.reserve("api.local", now).unwrap());
+            assert!(throttle.reserve("api.local", now).unwrap() < Duration::from_millis(1));
+        }
+    }
+}
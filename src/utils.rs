@@ -1,6 +1,8 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, str::FromStr, time::Duration};
 
+use hmac::{Hmac, Mac};
 use reqwest::header::{HeaderMap, HeaderName};
+use sha2::Sha256;
 
 use crate::{
     error::{FetchError, FetchResult},
@@ -53,7 +55,7 @@ pub fn reqwest_headers_to_map(header: &HeaderMap) -> FetchResult<FetchHeaders> {
         );
     }
 
-    return Ok(to_return);
+    Ok(to_return)
 }
 
 pub fn map_to_reqwest_headers(map: &FetchHeaders) -> FetchResult<HeaderMap> {
@@ -70,9 +72,118 @@ pub fn map_to_reqwest_headers(map: &FetchHeaders) -> FetchResult<HeaderMap> {
     Ok(headers)
 }
 
+/// Whole milliseconds in `duration`, rounding a partial millisecond up so a short timeout never
+/// becomes zero
+pub(crate) fn millis_rounded_up(duration: Duration) -> u64 {
+    let partial = !duration.subsec_nanos().is_multiple_of(1_000_000);
+    let millis = duration.as_millis() + u128::from(partial);
+    millis.try_into().unwrap_or(u64::MAX)
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Matches `text` against a pattern in which `*` stands for any sequence of characters
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Splits `key=value, key="quoted, value"` parameter lists, as found in auth challenges and rate
+/// limit headers
+pub(crate) fn parse_params(input: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        let key: String = std::iter::from_fn(|| chars.next_if(|c| *c != '=' && *c != ','))
+            .collect::<String>()
+            .trim()
+            .to_string();
+        if key.is_empty() {
+            break;
+        }
+        if chars.next_if_eq(&'=').is_none() {
+            continue;
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            value = std::iter::from_fn(|| chars.next_if(|c| *c != ','))
+                .collect::<String>()
+                .trim()
+                .to_string();
+        }
+        params.push((key, value));
+    }
+    params
+}
+
 #[cfg(test)]
 mod utils_tests {
-    use super::map_to_reqwest_headers;
+    use std::time::Duration;
+
+    use super::{glob_match, map_to_reqwest_headers, millis_rounded_up, parse_params};
+
+    #[test]
+    fn test_parse_params_with_quoted_commas() {
+        let params = parse_params(r#"realm="a, b", qop="auth", stale=FALSE"#);
+        assert_eq!(
+            vec![
+                ("realm".to_string(), "a, b".to_string()),
+                ("qop".to_string(), "auth".to_string()),
+                ("stale".to_string(), "FALSE".to_string()),
+            ],
+            params
+        );
+    }
+
+    #[test]
+    fn test_millis_rounded_up() {
+        assert_eq!(0, millis_rounded_up(Duration::ZERO));
+        assert_eq!(1, millis_rounded_up(Duration::from_micros(1)));
+        assert_eq!(5, millis_rounded_up(Duration::from_millis(5)));
+        assert_eq!(6, millis_rounded_up(Duration::from_micros(5_001)));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("/search", "/search"));
+        assert!(!glob_match("/search", "/search/1"));
+        assert!(glob_match("/search/*", "/search/1"));
+        assert!(glob_match("/users/*/orders", "/users/7/orders"));
+        assert!(!glob_match("/users/*/orders", "/users/7/invoices"));
+        assert!(glob_match("*", "/anything"));
+        assert!(!glob_match("/a*a", "/a"));
+    }
 
     #[test]
     fn test_map_macro_ident() {
@@ -102,7 +213,7 @@ mod utils_tests {
             value3 : "value"
         };
         let headers = map_to_reqwest_headers(&header_map);
-        assert_ne!(true, headers.is_err());
+        assert!(headers.is_ok());
     }
 }
  
\ No newline at end of file
@@ -0,0 +1,140 @@
+use crate::{
+    error::{FetchError, FetchResult},
+    FetchHeaders, FetchOptions,
+};
+
+/// The validators of a resource, used to make conditional updates (RFC 9110 section 13)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceVersion {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl ResourceVersion {
+    pub(crate) fn from_headers(headers: &FetchHeaders) -> Self {
+        Self {
+            etag: headers.get("etag").cloned(),
+            last_modified: headers.get("last-modified").cloned(),
+        }
+    }
+
+    /// Adds `If-Match` (or `If-Unmodified-Since` when there is no strong ETag) to the request
+    /// options. Weak ETags (`W/"..."`) are never sent, since `If-Match` only matches strong ones
+    pub(crate) fn precondition(&self, options: Option<FetchOptions>) -> FetchResult<FetchOptions> {
+        let strong_etag = self.etag.as_ref().filter(|etag| !etag.starts_with("W/"));
+        let (name, value) = match (strong_etag, &self.last_modified) {
+            (Some(etag), _) => ("if-match", etag),
+            (None, Some(last_modified)) => ("if-unmodified-since", last_modified),
+            (None, None) => return Err(FetchError::MissingValidator),
+        };
+
+        let mut options = options.unwrap_or_default();
+        options
+            .headers
+            .get_or_insert_with(Default::default)
+            .insert(name.to_string(), value.to_string());
+        Ok(options)
+    }
+}
+
+/// A resource together with the version it was read at.
+///
+/// Obtained from `Fetch::get_versioned` and passed back to `Fetch::put_versioned` or
+/// `Fetch::patch_versioned`, which only apply the update if the resource has not changed since.
+///
+/// # Example
+/// ```rust
+/// use httpmock::prelude::*;
+/// use rust_fetch::{Fetch, FetchError};
+///
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Document {
+///     title: String,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let server = MockServer::start();
+///     server.mock(|when, then| {
+///         when.method(GET).path("/documents/1");
+///         then.status(200)
+///             .header("etag", "\"v1\"")
+///             .json_body(serde_json::json!({ "title": "Draft" }));
+///     });
+///     server.mock(|when, then| {
+///         when.method(PUT).path("/documents/1").header("if-match", "\"v1\"");
+///         then.status(412).header("etag", "\"v2\"");
+///     });
+///
+///     let fetch = Fetch::new(&server.base_url(), None).unwrap();
+///     let mut document = fetch.get_versioned::<Document>("/documents/1", None).await.unwrap();
+///     document.value.title = "Final".to_string();
+///
+///     match fetch.put_versioned::<_, ()>("/documents/1", &document, None).await {
+///         Err(FetchError::PreconditionFailed { etag, .. }) => {
+///             assert_eq!(Some("\"v2\"".to_string()), etag)
+///         }
+///         other => panic!("expected a conflict, got {other:?}"),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Versioned<T> {
+    pub value: T,
+    pub version: ResourceVersion,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResourceVersion;
+    use crate::FetchError;
+
+    #[test]
+    fn test_precondition_prefers_etag() {
+        let version = ResourceVersion {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: Some("Sun, 11 Oct 2026 00:00:00 GMT".to_string()),
+        };
+        let headers = version.precondition(None).unwrap().headers.unwrap();
+        assert_eq!("\"v1\"", headers["if-match"]);
+        assert!(!headers.contains_key("if-unmodified-since"));
+    }
+
+    #[test]
+    fn test_precondition_falls_back_to_last_modified() {
+        let version = ResourceVersion {
+            etag: None,
+            last_modified: Some("Sun, 11 Oct 2026 00:00:00 GMT".to_string()),
+        };
+        let headers = version.precondition(None).unwrap().headers.unwrap();
+        assert_eq!("Sun, 11 Oct 2026 00:00:00 GMT", headers["if-unmodified-since"]);
+    }
+
+    #[test]
+    fn test_weak_etag_is_not_used_for_if_match() {
+        let version = ResourceVersion {
+            etag: Some("W/\"v1\"".to_string()),
+            last_modified: Some("Sun, 11 Oct 2026 00:00:00 GMT".to_string()),
+        };
+        let headers = version.precondition(None).unwrap().headers.unwrap();
+        assert!(!headers.contains_key("if-match"));
+        assert_eq!("Sun, 11 Oct 2026 00:00:00 GMT", headers["if-unmodified-since"]);
+
+        let version = ResourceVersion {
+            etag: Some("W/\"v1\"".to_string()),
+            last_modified: None,
+        };
+        assert!(matches!(
+            version.precondition(None),
+            Err(FetchError::MissingValidator)
+        ));
+    }
+
+    #[test]
+    fn test_precondition_requires_a_validator() {
+        assert!(matches!(
+            ResourceVersion::default().precondition(None),
+            Err(FetchError::MissingValidator)
+        ));
+    }
+}
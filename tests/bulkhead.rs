@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use httpmock::prelude::*;
+use rust_fetch::{Bulkhead, Fetch, FetchConfig, FetchError, FetchOptions};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_bulkhead_queues_and_rejects_overflow() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/slow");
+        then.status(200).delay(Duration::from_millis(300));
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            bulkhead: Some(Bulkhead::new(1, 1)),
+            ..Default::default()
+        }),
+    )?;
+
+    let (first, second, third) = tokio::join!(
+        fetch.get::<()>("/slow", no_body()),
+        fetch.get::<()>("/slow", no_body()),
+        fetch.get::<()>("/slow", no_body()),
+    );
+
+    let first = first?;
+    let second = second?;
+    assert_eq!(Some(Duration::ZERO), first.queue_wait);
+    assert!(second.queue_wait.unwrap() >= Duration::from_millis(250));
+    match third {
+        Err(FetchError::BulkheadFull { key }) => assert_eq!("127.0.0.1", key),
+        other => panic!("expected a full bulkhead, got {other:?}"),
+    }
+    mock.assert_hits_async(2).await;
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use std::time::Duration;
+
+use httpmock::prelude::*;
+use rust_fetch::{
+    map_string, CacheStatus, ContentDigestAlgorithm, Fetch, FetchConfig, FetchError, FetchOptions,
+    HttpCache,
+};
+
+fn cached_fetch(server: &MockServer) -> Fetch {
+    Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            cache: Some(HttpCache::memory(100)),
+            ..Default::default()
+        }),
+    )
+    .unwrap()
+}
+
+fn has_header(req: &HttpMockRequest, name: &str) -> bool {
+    req.headers
+        .as_ref()
+        .is_some_and(|headers| headers.iter().any(|(key, _)| key.eq_ignore_ascii_case(name)))
+}
+
+#[tokio::test]
+async fn test_fresh_response_is_served_from_cache() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/countries");
+        then.status(200)
+            .header("cache-control", "max-age=3600")
+            .json_body(serde_json::json!(["NL", "IT"]));
+    });
+
+    let fetch = cached_fetch(&server);
+    let first = fetch.get::<Vec<String>>("/countries", None).await?;
+    let second = fetch.get::<Vec<String>>("/countries", None).await?;
+
+    mock.assert_hits_async(1).await;
+    assert_eq!(first.body, second.body);
+    assert_eq!(CacheStatus::Network, first.cache_status);
+    assert_eq!(CacheStatus::Hit, second.cache_status);
+    assert_eq!(
+        "max-age=3600",
+        second.response_headers.get("cache-control").unwrap()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stale_response_is_revalidated_with_etag() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let full_mock = server.mock(|when, then| {
+        when.path("/rates")
+            .matches(|req| !has_header(req, "if-none-match"));
+        then.status(200)
+            .header("cache-control", "no-cache")
+            .header("etag", "\"v1\"")
+            .json_body(serde_json::json!({ "eur": 1.0 }));
+    });
+    let not_modified_mock = server.mock(|when, then| {
+        when.path("/rates").header("if-none-match", "\"v1\"");
+        then.status(304).header("etag", "\"v1\"");
+    });
+
+    let fetch = cached_fetch(&server);
+    fetch.get::<serde_json::Value>("/rates", None).await?;
+    let revalidated = fetch.get::<serde_json::Value>("/rates", None).await?;
+
+    full_mock.assert_hits_async(1).await;
+    not_modified_mock.assert_hits_async(1).await;
+    assert_eq!(200, revalidated.status);
+    assert_eq!(CacheStatus::Revalidated, revalidated.cache_status);
+    assert_eq!(serde_json::json!({ "eur": 1.0 }), revalidated.body.unwrap());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vary_selects_matching_variant() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/greeting");
+        then.status(200)
+            .header("cache-control", "max-age=3600")
+            .header("vary", "Accept-Language")
+            .json_body(serde_json::json!("hello"));
+    });
+
+    let fetch = cached_fetch(&server);
+    let with_language = |language: &str| {
+        Some(FetchOptions {
+            headers: Some(map_string! { "accept-language" => language }),
+            ..Default::default()
+        })
+    };
+
+    fetch.get::<String>("/greeting", with_language("en")).await?;
+    fetch.get::<String>("/greeting", with_language("en")).await?;
+    fetch.get::<String>("/greeting", with_language("it")).await?;
+
+    mock.assert_hits_async(2).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unsafe_request_invalidates_cached_get() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let get_mock = server.mock(|when, then| {
+        when.method(GET).path("/profile");
+        then.status(200)
+            .header("cache-control", "max-age=3600")
+            .json_body(serde_json::json!({ "name": "old" }));
+    });
+    server.mock(|when, then| {
+        when.method(PUT).path("/profile");
+        then.status(204);
+    });
+
+    let fetch = cached_fetch(&server);
+    fetch.get::<serde_json::Value>("/profile", None).await?;
+    fetch
+        .put::<_, ()>(
+            "/profile",
+            Some(serde_json::json!({ "name": "new" })),
+            Some(FetchOptions {
+                deserialize_body: false,
+                ..Default::default()
+            }),
+        )
+        .await?;
+    fetch.get::<serde_json::Value>("/profile", None).await?;
+
+    get_mock.assert_hits_async(2).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stale_while_revalidate_refreshes_in_background() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let old_mock = server.mock(|when, then| {
+        when.path("/dashboard");
+        then.status(200)
+            .header("cache-control", "max-age=0, stale-while-revalidate=60")
+            .json_body(serde_json::json!({ "version": 1 }));
+    });
+
+    let fetch = cached_fetch(&server);
+    fetch.get::<serde_json::Value>("/dashboard", None).await?;
+    old_mock.delete_async().await;
+    let new_mock = server.mock(|when, then| {
+        when.path("/dashboard");
+        then.status(200)
+            .header("cache-control", "max-age=3600")
+            .json_body(serde_json::json!({ "version": 2 }));
+    });
+
+    let stale = fetch.get::<serde_json::Value>("/dashboard", None).await?;
+    assert_eq!(CacheStatus::Stale, stale.cache_status);
+    assert_eq!(serde_json::json!({ "version": 1 }), stale.body.unwrap());
+
+    let mut refreshed = None;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let res = fetch.get::<serde_json::Value>("/dashboard", None).await?;
+        if res.cache_status == CacheStatus::Hit {
+            refreshed = res.body;
+            break;
+        }
+    }
+
+    assert_eq!(Some(serde_json::json!({ "version": 2 })), refreshed);
+    new_mock.assert_hits_async(1).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stale_if_error_serves_stale_response_on_server_error() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let ok_mock = server.mock(|when, then| {
+        when.path("/quotes");
+        then.status(200)
+            .header("cache-control", "max-age=0, stale-if-error=60")
+            .json_body(serde_json::json!(["ACME"]));
+    });
+
+    let fetch = cached_fetch(&server);
+    fetch.get::<Vec<String>>("/quotes", None).await?;
+    ok_mock.delete_async().await;
+    server.mock(|when, then| {
+        when.path("/quotes");
+        then.status(503);
+    });
+    server.mock(|when, then| {
+        when.path("/uncached");
+        then.status(503);
+    });
+
+    let res = fetch.get::<Vec<String>>("/quotes", None).await?;
+    assert_eq!(CacheStatus::Stale, res.cache_status);
+    assert_eq!(vec!["ACME"], res.body.unwrap());
+
+    let uncached = fetch.get::<Vec<String>>("/uncached", None).await;
+    assert!(matches!(uncached, Err(FetchError::NetworkError(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_body_failing_its_digest_is_not_cached() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/balance");
+        then.status(200)
+            .header("cache-control", "max-age=3600")
+            .header(
+                "content-digest",
+                ContentDigestAlgorithm::Sha256.header_value(br#"{"balance":42}"#),
+            )
+            .body(r#"{"balance":1000000}"#);
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            cache: Some(HttpCache::memory(100)),
+            verify_digests: true,
+            ..Default::default()
+        }),
+    )?;
+    for _ in 0..2 {
+        let result = fetch.get::<serde_json::Value>("/balance", None).await;
+        assert!(matches!(result, Err(FetchError::DigestMismatch { .. })));
+    }
+
+    mock.assert_hits_async(2).await;
+    Ok(())
+}
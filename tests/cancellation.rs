@@ -0,0 +1,98 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    time::{Duration, Instant},
+};
+
+use httpmock::prelude::*;
+use rust_fetch::{CancellationToken, Fetch, FetchConfig, FetchError, FetchOptions, RetryPolicy};
+
+fn cancellable(token: &CancellationToken) -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        cancellation_token: Some(token.clone()),
+        ..Default::default()
+    })
+}
+
+fn cancel_after(token: &CancellationToken, delay: Duration) {
+    let token = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        token.cancel();
+    });
+}
+
+#[tokio::test]
+async fn test_cancel_during_body_download() -> anyhow::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let base_url = format!("http://{}", listener.local_addr()?);
+    std::thread::spawn(move || {
+        let Ok((mut stream, _)) = listener.accept() else {
+            return;
+        };
+        let mut buf = [0u8; 4096];
+        let _ = stream.read(&mut buf);
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\n\r\nab");
+        std::thread::sleep(Duration::from_secs(2));
+    });
+
+    let token = CancellationToken::new();
+    cancel_after(&token, Duration::from_millis(100));
+    let started = Instant::now();
+    let result = Fetch::new(&base_url, None)?
+        .get::<()>("/export", cancellable(&token))
+        .await;
+
+    assert!(matches!(result, Err(FetchError::Cancelled)));
+    assert!(started.elapsed() < Duration::from_secs(1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_during_retry_backoff() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/flaky");
+        then.status(503).header("retry-after", "1");
+    });
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            retry: Some(
+                RetryPolicy::new(3).with_backoff(Duration::from_secs(1), Duration::from_secs(5)),
+            ),
+            ..Default::default()
+        }),
+    )?;
+
+    let token = CancellationToken::new();
+    cancel_after(&token, Duration::from_millis(100));
+    let started = Instant::now();
+    let result = fetch.get::<()>("/flaky", cancellable(&token)).await;
+
+    assert!(matches!(result, Err(FetchError::Cancelled)));
+    assert!(started.elapsed() < Duration::from_millis(900));
+    mock.assert_hits_async(1).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancelled_token_sends_nothing() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+    let parent = CancellationToken::new();
+    let token = parent.child_token();
+    parent.cancel();
+
+    let result = Fetch::new(&server.base_url(), None)?
+        .get::<()>("/orders", cancellable(&token))
+        .await;
+
+    assert!(matches!(result, Err(FetchError::Cancelled)));
+    mock.assert_hits_async(0).await;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use httpmock::prelude::*;
+use rust_fetch::{
+    CircuitBreaker, CircuitState, Fetch, FetchConfig, FetchError, FetchOptions, RateLimitMode,
+    RateLimiter,
+};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_circuit_opens_on_failures_and_recovers() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let failing_mock = server.mock(|when, then| {
+        when.path("/stock");
+        then.status(503);
+    });
+
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let recorded = changes.clone();
+    let breaker = CircuitBreaker::new()
+        .with_minimum_requests(3)
+        .with_cool_down(Duration::from_millis(200))
+        .on_state_change(move |_, _, to| recorded.lock().unwrap().push(to));
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            circuit_breaker: Some(breaker.clone()),
+            ..Default::default()
+        }),
+    )?;
+
+    for _ in 0..3 {
+        let res = fetch.get::<()>("/stock", no_body()).await;
+        assert!(matches!(res, Err(FetchError::NetworkError(_))));
+    }
+    match fetch.get::<()>("/stock", no_body()).await {
+        Err(FetchError::CircuitOpen { host, .. }) => assert_eq!("127.0.0.1", host),
+        other => panic!("expected an open circuit, got {other:?}"),
+    }
+    failing_mock.assert_hits_async(3).await;
+    assert_eq!(CircuitState::Open, breaker.state("127.0.0.1"));
+
+    failing_mock.delete_async().await;
+    server.mock(|when, then| {
+        when.path("/stock");
+        then.status(200);
+    });
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    fetch.get::<()>("/stock", no_body()).await?;
+    assert_eq!(CircuitState::Closed, breaker.state("127.0.0.1"));
+    assert_eq!(
+        vec![
+            CircuitState::Open,
+            CircuitState::HalfOpen,
+            CircuitState::Closed
+        ],
+        *changes.lock().unwrap()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_circuit_spends_no_rate_limit_quota() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/stock");
+        then.status(503);
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            circuit_breaker: Some(
+                CircuitBreaker::new()
+                    .with_minimum_requests(3)
+                    .with_cool_down(Duration::from_secs(60)),
+            ),
+            rate_limiter: Some(
+                RateLimiter::new(4, Duration::from_secs(60)).with_mode(RateLimitMode::FailFast),
+            ),
+            ..Default::default()
+        }),
+    )?;
+
+    for _ in 0..3 {
+        fetch.get::<()>("/stock", no_body()).await.unwrap_err();
+    }
+    for _ in 0..3 {
+        let res = fetch.get::<()>("/stock", no_body()).await;
+        assert!(matches!(res, Err(FetchError::CircuitOpen { .. })));
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use httpmock::prelude::*;
+use rust_fetch::{
+    reqwest::Method, ContentDigestAlgorithm, Fetch, FetchConfig, FetchError, FetchOptions,
+};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_request_content_digest_is_attached() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/payments")
+            .body(r#"{"amount":100}"#)
+            .header(
+                "content-digest",
+                ContentDigestAlgorithm::Sha256.header_value(br#"{"amount":100}"#),
+            );
+        then.status(201);
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            content_digest: Some(ContentDigestAlgorithm::Sha256),
+            ..Default::default()
+        }),
+    )?;
+
+    fetch
+        .post::<(), _>("/payments", Some(serde_json::json!({ "amount": 100 })), no_body())
+        .await?;
+
+    mock.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_response_content_digest_is_verified() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/valid");
+        then.status(200)
+            .header(
+                "content-digest",
+                ContentDigestAlgorithm::Sha512.header_value(br#"{"balance":42}"#),
+            )
+            .body(r#"{"balance":42}"#);
+    });
+    server.mock(|when, then| {
+        when.path("/tampered");
+        then.status(200)
+            .header(
+                "content-digest",
+                ContentDigestAlgorithm::Sha512.header_value(br#"{"balance":42}"#),
+            )
+            .body(r#"{"balance":1000000}"#);
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            verify_digests: true,
+            ..Default::default()
+        }),
+    )?;
+
+    let res = fetch.get::<serde_json::Value>("/valid", None).await?;
+    assert_eq!(serde_json::json!({ "balance": 42 }), res.body.unwrap());
+
+    match fetch.get::<()>("/tampered", no_body()).await {
+        Err(FetchError::DigestMismatch { header, algorithm }) => {
+            assert_eq!("content-digest", header);
+            assert_eq!("sha-512", algorithm);
+        }
+        other => panic!("expected a digest mismatch, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_digests_are_only_verified_when_asked_for() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/tampered");
+        then.status(200)
+            .header(
+                "content-digest",
+                ContentDigestAlgorithm::Sha256.header_value(br#"{"balance":42}"#),
+            )
+            .body(r#"{"balance":1000000}"#);
+    });
+
+    let fetch = Fetch::new(&server.base_url(), None)?;
+
+    let res = fetch.get::<()>("/tampered", no_body()).await?;
+    assert_eq!(200, res.status.as_u16());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bodiless_responses_are_not_verified() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(httpmock::Method::HEAD).path("/report");
+        then.status(200).header(
+            "content-digest",
+            ContentDigestAlgorithm::Sha256.header_value(b"quarterly report"),
+        );
+    });
+    server.mock(|when, then| {
+        when.method(DELETE).path("/report");
+        then.status(204).header(
+            "repr-digest",
+            ContentDigestAlgorithm::Sha256.header_value(b"quarterly report"),
+        );
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            verify_digests: true,
+            ..Default::default()
+        }),
+    )?;
+
+    fetch
+        .request(Method::HEAD, "/report")
+        .deserialize_body(false)
+        .send::<()>()
+        .await?;
+    fetch.delete::<(), ()>("/report", None, no_body()).await?;
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use std::{panic::AssertUnwindSafe, time::Duration};
+
+use httpmock::prelude::*;
+use rust_fetch::{
+    map_string, CacheStatus, ContentType, Fetch, FetchConfig, FetchError, FetchOptions, HttpCache,
+    TimeoutPhase,
+};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_updated_headers_reach_clones_and_scoped_handles() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let before_mock = server.mock(|when, then| {
+        when.path("/before").header("x-region", "eu");
+        then.status(200);
+    });
+    let after_mock = server.mock(|when, then| {
+        when.path("/after")
+            .header("x-version", "2")
+            .header("x-tenant", "acme")
+            .header("accept", "text/xml")
+            .matches(|req| {
+                !req.headers
+                    .iter()
+                    .flatten()
+                    .any(|(name, _)| name == "x-region")
+            });
+        then.status(200);
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            headers: Some(map_string! { "x-region" => "eu" }),
+            ..Default::default()
+        }),
+    )?;
+    let acme = fetch.with_headers(map_string! { "x-tenant" => "acme" });
+    acme.get::<()>("/before", no_body()).await?;
+
+    fetch.clone().update_defaults(|defaults| {
+        defaults.headers = map_string! { "x-version" => "2" };
+        defaults.accept = ContentType::TextXml;
+    })?;
+    acme.get::<()>("/after", no_body()).await?;
+
+    before_mock.assert_async().await;
+    after_mock.assert_async().await;
+    assert_eq!("2", acme.defaults().headers["x-version"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_swapped_headers_do_not_share_cached_responses() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mut mocks = Vec::new();
+    for tenant in ["acme", "globex", "initech"] {
+        mocks.push(server.mock(|when, then| {
+            when.path("/profile").header("x-tenant", tenant);
+            then.status(200)
+                .header("cache-control", "max-age=60")
+                .body(tenant);
+        }));
+    }
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            headers: Some(map_string! { "x-tenant" => "acme" }),
+            cache: Some(HttpCache::memory(16)),
+            ..Default::default()
+        }),
+    )?;
+    fetch.get::<()>("/profile", no_body()).await?;
+
+    fetch.update_defaults(|defaults| {
+        defaults.headers = map_string! { "x-tenant" => "globex" };
+    })?;
+    let res = fetch.get::<()>("/profile", no_body()).await?;
+    assert_eq!(CacheStatus::Network, res.cache_status);
+    assert_eq!(b"globex", &res.raw_body.unwrap()[..]);
+
+    fetch.update_defaults(|defaults| {
+        defaults.headers = map_string! { "x-tenant" => "acme" };
+    })?;
+    let res = fetch.get::<()>("/profile", no_body()).await?;
+    assert_eq!(CacheStatus::Hit, res.cache_status);
+    assert_eq!(b"acme", &res.raw_body.unwrap()[..]);
+
+    // Per-call headers are part of the partition as well
+    let res = fetch
+        .get::<()>(
+            "/profile",
+            Some(FetchOptions {
+                headers: Some(map_string! { "x-tenant" => "initech" }),
+                deserialize_body: false,
+                ..Default::default()
+            }),
+        )
+        .await?;
+    assert_eq!(CacheStatus::Network, res.cache_status);
+    assert_eq!(b"initech", &res.raw_body.unwrap()[..]);
+    for mock in mocks {
+        mock.assert_hits_async(1).await;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_in_flight_calls_keep_their_timeout() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/slow");
+        then.status(200).delay(Duration::from_millis(300));
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            timeout_ms: Some(2_000),
+            ..Default::default()
+        }),
+    )?;
+    let in_flight = {
+        let fetch = fetch.clone();
+        tokio::spawn(async move { fetch.get::<()>("/slow", no_body()).await })
+    };
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    fetch.update_defaults(|defaults| defaults.timeout_ms = Some(50))?;
+
+    assert!(in_flight.await?.is_ok());
+    match fetch.get::<()>("/slow", no_body()).await {
+        Err(FetchError::Timeout {
+            phase: TimeoutPhase::Request,
+            after,
+        }) => assert_eq!(Duration::from_millis(50), after),
+        other => panic!("expected a request timeout, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_timeout_can_be_removed() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/slow");
+        then.status(200).delay(Duration::from_millis(300));
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            timeout_ms: Some(50),
+            ..Default::default()
+        }),
+    )?;
+    let res = fetch.get::<()>("/slow", no_body()).await;
+    assert!(matches!(res, Err(FetchError::Timeout { .. })));
+
+    fetch.update_defaults(|defaults| defaults.timeout_ms = None)?;
+    fetch.get::<()>("/slow", no_body()).await?;
+    Ok(())
+}
+
+#[test]
+fn test_invalid_update_keeps_the_defaults() -> anyhow::Result<()> {
+    let fetch = Fetch::new("http://localhost", None)?;
+    fetch.set_default_headers(Some(map_string! { "x-tenant" => "acme" }))?;
+
+    let result = fetch.update_defaults(|defaults| {
+        defaults
+            .headers
+            .insert("x-bad".to_string(), "line\nbreak".to_string());
+        defaults.timeout_ms = Some(50);
+    });
+
+    assert!(matches!(result, Err(FetchError::HeaderParseError(name, _)) if name == "x-bad"));
+    let defaults = fetch.defaults();
+    assert_eq!("acme", defaults.headers["x-tenant"]);
+    assert!(!defaults.headers.contains_key("x-bad"));
+    assert_eq!(None, defaults.timeout_ms);
+    Ok(())
+}
+
+#[test]
+fn test_panicking_update_leaves_the_defaults_usable() -> anyhow::Result<()> {
+    let fetch = Fetch::new("http://localhost", None)?;
+    let panicked = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        let _ = fetch.update_defaults(|defaults| {
+            defaults.timeout_ms = Some(50);
+            panic!("update failed");
+        });
+    }));
+    assert!(panicked.is_err());
+
+    assert_eq!(None, fetch.defaults().timeout_ms);
+    fetch.update_defaults(|defaults| defaults.timeout_ms = Some(100))?;
+    assert_eq!(Some(100), fetch.defaults().timeout_ms);
+    Ok(())
+}
+
+#[test]
+fn test_update_can_read_the_defaults() -> anyhow::Result<()> {
+    let fetch = Fetch::new(
+        "http://localhost",
+        Some(FetchConfig {
+            timeout_ms: Some(1_000),
+            ..Default::default()
+        }),
+    )?;
+
+    fetch.update_defaults(|defaults| {
+        defaults.timeout_ms = fetch.defaults().timeout_ms.map(|timeout| timeout * 2)
+    })?;
+
+    assert_eq!(Some(2_000), fetch.defaults().timeout_ms);
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use httpmock::prelude::*;
+use rust_fetch::{DigestAuth, Fetch, FetchAuth, FetchConfig, FetchError, FetchOptions};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+fn authorization(req: &HttpMockRequest) -> Option<String> {
+    req.headers
+        .as_ref()?
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+        .map(|(_, value)| value.clone())
+}
+
+fn digest_fetch(server: &MockServer) -> Fetch {
+    Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            auth: Some(FetchAuth::Digest(DigestAuth::new("admin", "secret"))),
+            ..Default::default()
+        }),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_digest_challenge_is_answered_and_reused() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let challenge_mock = server.mock(|when, then| {
+        when.path("/status")
+            .matches(|req| authorization(req).is_none());
+        then.status(401).header(
+            "www-authenticate",
+            r#"Digest realm="device", qop="auth", algorithm=SHA-256, nonce="abc123", opaque="xyz""#,
+        );
+    });
+    let first_mock = server.mock(|when, then| {
+        when.path("/status")
+            .matches(|req| {
+                authorization(req).is_some_and(|value| {
+                    value.starts_with(r#"Digest username="admin", realm="device", nonce="abc123", uri="/status", algorithm=SHA-256, response=""#)
+                        && value.contains("qop=auth, nc=00000001, cnonce=")
+                        && value.ends_with(r#"opaque="xyz""#)
+                })
+            });
+        then.status(200);
+    });
+    let second_mock = server.mock(|when, then| {
+        when.path("/status")
+            .matches(|req| authorization(req).is_some_and(|value| value.contains("nc=00000002")));
+        then.status(200);
+    });
+
+    let fetch = digest_fetch(&server);
+
+    let res = fetch.get::<()>("/status", no_body()).await?;
+    assert_eq!(200, res.status);
+    fetch.get::<()>("/status", no_body()).await?;
+
+    challenge_mock.assert_hits_async(1).await;
+    first_mock.assert_hits_async(1).await;
+    second_mock.assert_hits_async(1).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_digest_without_challenge_returns_401() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/status");
+        then.status(401)
+            .header("www-authenticate", r#"Basic realm="device""#);
+    });
+
+    let res = digest_fetch(&server).get::<()>("/status", no_body()).await;
+
+    mock.assert_hits_async(1).await;
+    match res {
+        Err(FetchError::NetworkError(err)) => assert_eq!(401, err.status_code),
+        other => panic!("expected a 401 NetworkError, got {other:?}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use httpmock::prelude::*;
+use rust_fetch::{
+    DiscoverySource, EndpointSet, Fetch, FetchError, FetchOptions, FetchResult, FileDiscovery,
+};
+
+fn endpoint_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rust-fetch-it-{}-{name}", std::process::id()))
+}
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_endpoints_follow_the_file() -> anyhow::Result<()> {
+    let blue = MockServer::start();
+    let green = MockServer::start();
+    let blue_mock = blue.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+    let green_mock = green.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+
+    let path = endpoint_file("rollout.toml");
+    std::fs::write(&path, format!("endpoints = [\"{}\"]", blue.base_url()))?;
+    let endpoints = EndpointSet::new(Vec::<String>::new());
+    endpoints.discover_from(FileDiscovery::new(&path), Duration::from_millis(20))?;
+    let fetch = Fetch::from_endpoints(endpoints.clone(), None)?;
+
+    fetch.get::<()>("/orders", no_body()).await?;
+    blue_mock.assert_hits_async(1).await;
+
+    std::fs::write(&path, format!("endpoints = [\"{}\"]", green.base_url()))?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(vec![green.base_url()], endpoints.urls());
+    fetch.get::<()>("/orders", no_body()).await?;
+    green_mock.assert_hits_async(1).await;
+
+    // A broken file keeps the last known endpoints
+    std::fs::write(&path, "endpoints = [")?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    fetch.get::<()>("/orders", no_body()).await?;
+    green_mock.assert_hits_async(2).await;
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_file_fails_discovery() {
+    let endpoints = EndpointSet::new(Vec::<String>::new());
+    let result = endpoints.discover_from(
+        FileDiscovery::new(endpoint_file("missing.json")),
+        Duration::from_secs(1),
+    );
+    assert!(matches!(result, Err(FetchError::DiscoveryError(_))));
+
+    let fetch = Fetch::from_endpoints(endpoints, None).unwrap();
+    assert!(matches!(
+        fetch.get::<()>("/orders", no_body()).await,
+        Err(FetchError::NoEndpoints)
+    ));
+}
+
+/// A source whose checks after the first one take half a second
+#[derive(Debug, Default)]
+struct SlowSource {
+    checks: AtomicUsize,
+}
+
+impl DiscoverySource for SlowSource {
+    fn discover(&self) -> FetchResult<Vec<String>> {
+        if self.checks.fetch_add(1, Ordering::SeqCst) > 0 {
+            std::thread::sleep(Duration::from_millis(500));
+        }
+        Ok(vec!["http://orders.local".to_string()])
+    }
+}
+
+#[tokio::test]
+async fn test_slow_source_does_not_block_the_runtime() -> anyhow::Result<()> {
+    let endpoints = EndpointSet::new(Vec::<String>::new());
+    endpoints.discover_from(SlowSource::default(), Duration::from_millis(10))?;
+
+    let started = Instant::now();
+    for _ in 0..5 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    assert!(started.elapsed() < Duration::from_millis(400));
+    assert_eq!(vec!["http://orders.local"], endpoints.urls());
+    Ok(())
+}
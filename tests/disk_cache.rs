@@ -0,0 +1,95 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::Arc,
+};
+
+use httpmock::prelude::*;
+use rust_fetch::{CacheStatus, DiskCacheStore, Fetch, FetchConfig, FetchError, HttpCache};
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("rust-fetch-it-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn disk_fetch(base_url: &str, dir: &std::path::Path, offline_fallback: bool) -> Fetch {
+    let store = DiskCacheStore::new(dir, 1024 * 1024).unwrap();
+    Fetch::new(
+        base_url,
+        Some(FetchConfig {
+            cache: Some(HttpCache::new(Arc::new(store)).with_offline_fallback(offline_fallback)),
+            ..Default::default()
+        }),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_cached_responses_survive_restarts() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/sites");
+        then.status(200)
+            .header("cache-control", "max-age=3600")
+            .json_body(serde_json::json!(["north", "south"]));
+    });
+
+    let dir = temp_dir("restart");
+    disk_fetch(&server.base_url(), &dir, false)
+        .get::<Vec<String>>("/sites", None)
+        .await?;
+    let res = disk_fetch(&server.base_url(), &dir, false)
+        .get::<Vec<String>>("/sites", None)
+        .await?;
+
+    mock.assert_hits_async(1).await;
+    assert_eq!(vec!["north", "south"], res.body.unwrap());
+    assert_eq!(CacheStatus::Hit, res.cache_status);
+    Ok(())
+}
+
+/// Serves a single cacheable response and then stops listening. (Pooled `MockServer`s keep their
+/// port open after being dropped, so they cannot simulate an unreachable server.)
+fn serve_once(body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0u8; 4096];
+        let _ = stream.read(&mut buf);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncache-control: max-age=0\r\n\
+             etag: \"v1\"\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).unwrap();
+    });
+    base_url
+}
+
+#[tokio::test]
+async fn test_offline_fallback_serves_stale_response() -> anyhow::Result<()> {
+    let dir = temp_dir("offline");
+    let base_url = serve_once(r#"{"celsius":21}"#);
+
+    let res = disk_fetch(&base_url, &dir, true)
+        .get::<serde_json::Value>("/readings", None)
+        .await?;
+    assert_eq!(CacheStatus::Network, res.cache_status);
+
+    let res = disk_fetch(&base_url, &dir, true)
+        .get::<serde_json::Value>("/readings", None)
+        .await?;
+    assert_eq!(CacheStatus::Stale, res.cache_status);
+    assert_eq!(serde_json::json!({ "celsius": 21 }), res.body.unwrap());
+
+    let without_fallback = disk_fetch(&base_url, &dir, false)
+        .get::<serde_json::Value>("/readings", None)
+        .await;
+    assert!(matches!(
+        without_fallback,
+        Err(FetchError::UnableToSendRequest { .. })
+    ));
+    Ok(())
+}
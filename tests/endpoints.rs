@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use httpmock::prelude::*;
+use rust_fetch::{
+    CacheStatus, EndpointSet, Fetch, FetchConfig, FetchError, FetchOptions, HttpCache, RetryPolicy,
+    SelectionStrategy,
+};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_round_robin_spreads_requests() -> anyhow::Result<()> {
+    let first = MockServer::start();
+    let second = MockServer::start();
+    let first_mock = first.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+    let second_mock = second.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+
+    let fetch = Fetch::from_endpoints(
+        EndpointSet::new([first.base_url(), second.base_url()]),
+        None,
+    )?;
+    for _ in 0..4 {
+        fetch.get::<()>("/orders", no_body()).await?;
+    }
+
+    first_mock.assert_hits_async(2).await;
+    second_mock.assert_hits_async(2).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failing_primary_is_ejected_and_probed() -> anyhow::Result<()> {
+    let primary = MockServer::start();
+    let secondary = MockServer::start();
+    let failing = primary.mock(|when, then| {
+        when.path("/orders");
+        then.status(503);
+    });
+    let secondary_mock = secondary.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+
+    let endpoints = EndpointSet::new([primary.base_url(), secondary.base_url()])
+        .with_strategy(SelectionStrategy::PrimaryFailover)
+        .with_ejection(2, Duration::from_millis(100));
+    let fetch = Fetch::from_endpoints(endpoints.clone(), None)?;
+
+    for _ in 0..2 {
+        match fetch.get::<()>("/orders", no_body()).await {
+            Err(FetchError::NetworkError(err)) => assert_eq!(503, err.status_code),
+            other => panic!("expected a 503, got {other:?}"),
+        }
+    }
+    assert_eq!(vec![secondary.base_url()], endpoints.healthy());
+    fetch.get::<()>("/orders", no_body()).await?;
+    failing.assert_hits_async(2).await;
+    secondary_mock.assert_hits_async(1).await;
+
+    failing.delete_async().await;
+    let recovered = primary.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    fetch.get::<()>("/orders", no_body()).await?;
+    fetch.get::<()>("/orders", no_body()).await?;
+
+    recovered.assert_hits_async(2).await;
+    assert_eq!(2, endpoints.healthy().len());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retry_goes_to_another_endpoint() -> anyhow::Result<()> {
+    let primary = MockServer::start();
+    let secondary = MockServer::start();
+    let failing = primary.mock(|when, then| {
+        when.path("/orders");
+        then.status(503);
+    });
+    let secondary_mock = secondary.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+
+    let fetch = Fetch::from_endpoints(
+        EndpointSet::new([primary.base_url(), secondary.base_url()])
+            .with_strategy(SelectionStrategy::PrimaryFailover),
+        Some(FetchConfig {
+            retry: Some(
+                RetryPolicy::new(1)
+                    .with_backoff(Duration::from_millis(1), Duration::from_millis(1)),
+            ),
+            ..Default::default()
+        }),
+    )?;
+
+    let res = fetch.get::<()>("/orders", no_body()).await?;
+    assert_eq!(200, res.status);
+    failing.assert_hits_async(1).await;
+    secondary_mock.assert_hits_async(1).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replicas_share_cached_responses() -> anyhow::Result<()> {
+    let first = MockServer::start();
+    let second = MockServer::start();
+    let mut get_mocks = Vec::new();
+    for server in [&first, &second] {
+        get_mocks.push(server.mock(|when, then| {
+            when.method(GET).path("/orders/1");
+            then.status(200)
+                .header("cache-control", "max-age=60")
+                .body("order");
+        }));
+        server.mock(|when, then| {
+            when.method(PUT).path("/orders/1");
+            then.status(204);
+        });
+    }
+
+    let fetch = Fetch::from_endpoints(
+        EndpointSet::new([first.base_url(), second.base_url()]),
+        Some(FetchConfig {
+            cache: Some(HttpCache::memory(16)),
+            ..Default::default()
+        }),
+    )?;
+    let res = fetch.get::<()>("/orders/1", no_body()).await?;
+    assert_eq!(CacheStatus::Network, res.cache_status);
+    let res = fetch.get::<()>("/orders/1", no_body()).await?;
+    assert_eq!(CacheStatus::Hit, res.cache_status);
+    assert_eq!(1, get_mocks[0].hits() + get_mocks[1].hits());
+
+    // The PUT goes to the other replica, but still invalidates the shared entry
+    fetch
+        .put::<_, ()>("/orders/1", Some(serde_json::json!({})), no_body())
+        .await?;
+    let res = fetch.get::<()>("/orders/1", no_body()).await?;
+    assert_eq!(CacheStatus::Network, res.cache_status);
+    assert_eq!(2, get_mocks[0].hits() + get_mocks[1].hits());
+    Ok(())
+}
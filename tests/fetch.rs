@@ -208,7 +208,7 @@ async fn test_fetch_post() -> anyhow::Result<()> {
         .post::<NetworkTestResponse, ()>("/test", None, None)
         .await;
 
-    assert_eq!(false, response.is_err());
+    assert!(response.is_ok());
 
     let response = response.unwrap();
 
@@ -0,0 +1,144 @@
+use httpmock::prelude::*;
+use rust_fetch::{
+    reqwest::redirect::Policy, ContentDigestAlgorithm, Fetch, FetchConfig, FetchOptions,
+};
+use serde::Deserialize;
+
+/// `{"ok":true}`, gzip encoded
+const GZIPPED: [u8; 31] = [
+    31, 139, 8, 0, 0, 0, 0, 0, 2, 255, 171, 86, 202, 207, 86, 178, 42, 41, 42, 77, 173, 5, 0, 144,
+    95, 212, 167, 11, 0, 0, 0,
+];
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct Status {
+    ok: bool,
+}
+
+#[tokio::test]
+async fn test_builder_decompresses_when_enabled() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/status")
+            .header("x-tenant", "acme")
+            .header_exists("accept-encoding");
+        then.status(200)
+            .header("content-type", "application/json")
+            .header("content-encoding", "gzip")
+            .body(GZIPPED);
+    });
+
+    let fetch = Fetch::builder()
+        .base_url(&server.base_url())
+        .header("x-tenant", "acme")
+        .gzip(true)
+        .build()?;
+    let res = fetch.get::<Status>("/status", None).await?;
+
+    mock.assert_async().await;
+    assert!(res.body.unwrap().ok);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_new_decompresses_by_default() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/status").header_exists("accept-encoding");
+        then.status(200)
+            .header("content-type", "application/json")
+            .header("content-encoding", "gzip")
+            .body(GZIPPED);
+    });
+
+    let res = Fetch::new(&server.base_url(), None)?
+        .get::<Status>("/status", None)
+        .await?;
+    assert!(res.body.unwrap().ok);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_builder_can_turn_decompression_off() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/status");
+        then.status(200)
+            .header("content-encoding", "gzip")
+            .body(GZIPPED);
+    });
+
+    let res = Fetch::builder()
+        .base_url(&server.base_url())
+        .gzip(false)
+        .build()?
+        .get::<()>("/status", no_body())
+        .await?;
+    assert_eq!(&GZIPPED[..], &res.raw_body.unwrap()[..]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_digests_are_checked_against_the_body_as_sent() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/status");
+        then.status(200)
+            .header("content-encoding", "gzip")
+            .header(
+                "content-digest",
+                ContentDigestAlgorithm::Sha256.header_value(&GZIPPED),
+            )
+            .body(GZIPPED);
+    });
+
+    let res = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            verify_digests: true,
+            ..Default::default()
+        }),
+    )?
+    .get::<()>("/status", no_body())
+    .await?;
+    assert_eq!(&GZIPPED[..], &res.raw_body.unwrap()[..]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_builder_redirect_policy() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/old");
+        then.status(302).header("location", "/new");
+    });
+    let new = server.mock(|when, then| {
+        when.path("/new");
+        then.status(200);
+    });
+
+    let fetch = Fetch::builder()
+        .base_url(&server.base_url())
+        .redirect(Policy::none())
+        .build()?;
+    let res = fetch
+        .get::<()>(
+            "/old",
+            Some(FetchOptions {
+                deserialize_body: false,
+                ..Default::default()
+            }),
+        )
+        .await?;
+
+    assert_eq!(302, res.status);
+    new.assert_hits_async(0).await;
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use rust_fetch::{
+    reqwest::Method, Bulkhead, Fetch, FetchConfig, FetchOptions, HedgePolicy, RateLimiter,
+};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+const OK: &[u8] = b"HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 0\r\n\r\n";
+const UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nconnection: close\r\ncontent-length: 0\r\n\r\n";
+
+/// Answers the n-th request with the n-th scripted response after its delay, and any further
+/// requests with `200 OK` right away. (The mock server cannot answer identical requests
+/// differently.)
+fn scripted_server(script: Vec<(Duration, &'static [u8])>) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let script = Arc::new(script);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { return };
+            let counter = counter.clone();
+            let script = script.clone();
+            std::thread::spawn(move || {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let (delay, response) = script.get(n).copied().unwrap_or((Duration::ZERO, OK));
+                std::thread::sleep(delay);
+                let _ = stream.write_all(response);
+            });
+        }
+    });
+    (base_url, requests)
+}
+
+/// Serves `200 OK` on every request, but the first one only after a second
+fn slow_first_server() -> (String, Arc<AtomicUsize>) {
+    scripted_server(vec![(Duration::from_secs(1), OK)])
+}
+
+fn hedged_fetch(base_url: &str) -> Fetch {
+    fetch_with(
+        base_url,
+        FetchConfig {
+            hedging: Some(HedgePolicy::new(Duration::from_millis(100))),
+            ..Default::default()
+        },
+    )
+}
+
+fn fetch_with(base_url: &str, config: FetchConfig) -> Fetch {
+    Fetch::new(base_url, Some(config)).unwrap()
+}
+
+#[tokio::test]
+async fn test_slow_get_is_hedged() -> anyhow::Result<()> {
+    let (base_url, requests) = slow_first_server();
+
+    let started = Instant::now();
+    let res = hedged_fetch(&base_url)
+        .get::<()>("/replica", no_body())
+        .await?;
+
+    assert_eq!(200, res.status);
+    assert!(started.elapsed() < Duration::from_millis(700));
+    assert_eq!(2, requests.load(Ordering::SeqCst));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_post_is_never_hedged() -> anyhow::Result<()> {
+    let (base_url, requests) = slow_first_server();
+
+    let started = Instant::now();
+    hedged_fetch(&base_url)
+        .post::<(), _>("/orders", Some(serde_json::json!({})), no_body())
+        .await?;
+
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert_eq!(1, requests.load(Ordering::SeqCst));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_put_is_only_hedged_when_opted_in() -> anyhow::Result<()> {
+    let (base_url, requests) = slow_first_server();
+    hedged_fetch(&base_url)
+        .put::<_, ()>("/orders/1", Some(serde_json::json!({})), no_body())
+        .await?;
+    assert_eq!(1, requests.load(Ordering::SeqCst));
+
+    let (base_url, requests) = slow_first_server();
+    let fetch = fetch_with(
+        &base_url,
+        FetchConfig {
+            hedging: Some(HedgePolicy::new(Duration::from_millis(100)).with_methods([Method::PUT])),
+            ..Default::default()
+        },
+    );
+    fetch
+        .put::<_, ()>("/orders/1", Some(serde_json::json!({})), no_body())
+        .await?;
+    assert_eq!(2, requests.load(Ordering::SeqCst));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_error_does_not_win_the_race() -> anyhow::Result<()> {
+    let (base_url, requests) = scripted_server(vec![
+        (Duration::from_millis(300), UNAVAILABLE),
+        (Duration::from_millis(400), OK),
+    ]);
+
+    let res = hedged_fetch(&base_url)
+        .get::<()>("/replica", no_body())
+        .await?;
+
+    assert_eq!(200, res.status);
+    assert_eq!(2, requests.load(Ordering::SeqCst));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hedge_needs_a_free_bulkhead_slot() -> anyhow::Result<()> {
+    let (base_url, requests) = slow_first_server();
+    let fetch = fetch_with(
+        &base_url,
+        FetchConfig {
+            hedging: Some(HedgePolicy::new(Duration::from_millis(100))),
+            bulkhead: Some(Bulkhead::new(1, 0)),
+            ..Default::default()
+        },
+    );
+
+    let started = Instant::now();
+    fetch.get::<()>("/replica", no_body()).await?;
+
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert_eq!(1, requests.load(Ordering::SeqCst));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hedge_needs_rate_limit_quota() -> anyhow::Result<()> {
+    let (base_url, requests) = slow_first_server();
+    let fetch = fetch_with(
+        &base_url,
+        FetchConfig {
+            hedging: Some(HedgePolicy::new(Duration::from_millis(100))),
+            rate_limiter: Some(RateLimiter::new(1, Duration::from_secs(60))),
+            ..Default::default()
+        },
+    );
+
+    let started = Instant::now();
+    fetch.get::<()>("/replica", no_body()).await?;
+
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert_eq!(1, requests.load(Ordering::SeqCst));
+    Ok(())
+}
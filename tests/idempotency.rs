@@ -0,0 +1,137 @@
+use httpmock::prelude::*;
+use rust_fetch::{
+    DigestAuth, Fetch, FetchAuth, FetchConfig, FetchError, FetchOptions, IdempotencyKey,
+};
+
+fn idempotent(key: IdempotencyKey) -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        idempotency_key: Some(key),
+        ..Default::default()
+    })
+}
+
+fn header(req: &HttpMockRequest, name: &str) -> Option<String> {
+    req.headers
+        .as_ref()?
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.clone())
+}
+
+#[tokio::test]
+async fn test_generated_key_is_sent_and_reported() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/payments")
+            .matches(|req| header(req, "idempotency-key").is_some_and(|key| key.len() == 36));
+        then.status(201);
+    });
+
+    let fetch = Fetch::new(&server.base_url(), None)?;
+    let first = fetch
+        .post::<(), _>(
+            "/payments",
+            Some(serde_json::json!({ "amount": 5 })),
+            idempotent(IdempotencyKey::Auto),
+        )
+        .await?;
+    let second = fetch
+        .post::<(), _>(
+            "/payments",
+            Some(serde_json::json!({ "amount": 5 })),
+            idempotent(IdempotencyKey::Auto),
+        )
+        .await?;
+
+    mock.assert_hits_async(2).await;
+    assert!(first.idempotency_key.is_some());
+    assert_ne!(first.idempotency_key, second.idempotency_key);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_supplied_key_is_reported_on_errors() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/payments")
+            .header("idempotency-key", "order-42");
+        then.status(503);
+    });
+
+    let fetch = Fetch::new(&server.base_url(), None)?;
+    let err = fetch
+        .post::<(), _>(
+            "/payments",
+            Some(serde_json::json!({ "amount": 5 })),
+            idempotent(IdempotencyKey::Key("order-42".to_string())),
+        )
+        .await
+        .unwrap_err();
+
+    assert_eq!(Some("order-42"), err.idempotency_key());
+    assert!(matches!(err.cause(), FetchError::NetworkError(_)));
+    match err.into_inner() {
+        FetchError::NetworkError(err) => assert_eq!(503, err.status_code),
+        other => panic!("expected a 503 NetworkError, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_key_is_reused_when_request_is_retried() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let challenge_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/payments")
+            .header("idempotency-key", "order-7")
+            .matches(|req| header(req, "authorization").is_none());
+        then.status(401).header(
+            "www-authenticate",
+            r#"Digest realm="payments", qop="auth", nonce="n1""#,
+        );
+    });
+    let authorized_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/payments")
+            .header("idempotency-key", "order-7")
+            .matches(|req| header(req, "authorization").is_some());
+        then.status(201);
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            auth: Some(FetchAuth::Digest(DigestAuth::new("shop", "secret"))),
+            ..Default::default()
+        }),
+    )?;
+    let res = fetch
+        .post::<(), _>(
+            "/payments",
+            Some(serde_json::json!({ "amount": 5 })),
+            idempotent(IdempotencyKey::Key("order-7".to_string())),
+        )
+        .await?;
+
+    challenge_mock.assert_hits_async(1).await;
+    authorized_mock.assert_hits_async(1).await;
+    assert_eq!(Some("order-7".to_string()), res.idempotency_key);
+    Ok(())
+}
+
+#[test]
+fn test_into_inner_removes_all_wrappers() {
+    let err = FetchError::Idempotent {
+        key: "order-9".to_string(),
+        source: Box::new(FetchError::RetryBudgetExhausted {
+            attempts: 3,
+            source: Box::new(FetchError::NoEndpoints),
+        }),
+    };
+
+    assert!(matches!(err.cause(), FetchError::NoEndpoints));
+    assert!(matches!(err.into_inner(), FetchError::NoEndpoints));
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use httpmock::prelude::*;
+use rust_fetch::{
+    Ed25519Key, Fetch, FetchConfig, FetchError, FetchOptions, HmacSha256Key, MessageSigner,
+    MessageVerifier, SignatureComponent, SignatureKey,
+};
+
+fn header(req: &HttpMockRequest, name: &str) -> Option<String> {
+    req.headers
+        .as_ref()?
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.clone())
+}
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_ed25519_signed_request() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/orders").matches(|req| {
+            header(req, "date").is_some()
+                && header(req, "signature-input").is_some_and(|value| {
+                    value.starts_with(r#"sig1=("@method" "@path" "@query" "date");created="#)
+                        && value.ends_with(r#";keyid="partner-key";alg="ed25519""#)
+                })
+                && header(req, "signature")
+                    .is_some_and(|value| value.starts_with("sig1=:") && value.ends_with(':'))
+        });
+        then.status(200);
+    });
+
+    let key = Ed25519Key::from_secret("partner-key", &[7u8; 32]);
+    let signer = MessageSigner::new(Arc::new(key))
+        .with_components([
+            SignatureComponent::Method,
+            SignatureComponent::Path,
+            SignatureComponent::Query,
+            SignatureComponent::header("date"),
+        ])
+        .with_algorithm_parameter(true);
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            signer: Some(Arc::new(signer)),
+            ..Default::default()
+        }),
+    )?;
+
+    fetch.get::<()>("/orders", no_body()).await?;
+
+    mock.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_covered_header_fails_signing() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+
+    let signer = MessageSigner::new(Arc::new(HmacSha256Key::new("partner-key", b"secret")))
+        .with_components([
+            SignatureComponent::Method,
+            SignatureComponent::header("content-digest"),
+        ]);
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            signer: Some(Arc::new(signer)),
+            ..Default::default()
+        }),
+    )?;
+
+    let result = fetch.get::<()>("/orders", no_body()).await;
+    assert!(matches!(result, Err(FetchError::SigningError(_))));
+    mock.assert_hits_async(0).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify_signed_response() -> anyhow::Result<()> {
+    let key = Arc::new(HmacSha256Key::new("server-key", b"shared-secret"));
+    let params = r#"("@status" "content-type");created=1700000000;keyid="server-key""#;
+    let base = format!(
+        "\"@status\": 200\n\"content-type\": application/json\n\"@signature-params\": {params}"
+    );
+    let signature = STANDARD.encode(key.sign(base.as_bytes())?);
+
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/signed");
+        then.status(200)
+            .header("content-type", "application/json")
+            .header("signature-input", format!("sig1={params}"))
+            .header("signature", format!("sig1=:{signature}:"))
+            .body("{}");
+    });
+    server.mock(|when, then| {
+        when.path("/tampered");
+        then.status(200)
+            .header("content-type", "text/plain")
+            .header("signature-input", format!("sig1={params}"))
+            .header("signature", format!("sig1=:{signature}:"))
+            .body("{}");
+    });
+
+    let fetch = Fetch::new(&server.base_url(), None)?;
+    let verifier = MessageVerifier::new(key);
+
+    let res = fetch.get::<()>("/signed", no_body()).await?;
+    verifier.verify(&res)?;
+
+    let res = fetch.get::<()>("/tampered", no_body()).await?;
+    assert!(matches!(
+        verifier.verify(&res),
+        Err(FetchError::InvalidSignature(_))
+    ));
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::sync::{Arc, Mutex};
+
+use httpmock::prelude::*;
+use rust_fetch::{
+    reqwest::{header::HeaderValue, Request, Response},
+    Fetch, FetchError, FetchOptions, FetchResult, Middleware,
+};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+/// Tags requests with its name and records the order it was called in
+#[derive(Debug)]
+struct Recorder {
+    name: &'static str,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl Middleware for Recorder {
+    fn on_request(&self, request: &mut Request) -> FetchResult<()> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("{} request", self.name));
+        request
+            .headers_mut()
+            .append("x-middleware", HeaderValue::from_static(self.name));
+        Ok(())
+    }
+
+    fn on_response(&self, response: &mut Response) -> FetchResult<()> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("{} response", self.name));
+        response
+            .headers_mut()
+            .insert("x-seen-by", HeaderValue::from_static(self.name));
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Reject;
+
+impl Middleware for Reject {
+    fn on_request(&self, _request: &mut Request) -> FetchResult<()> {
+        Err(FetchError::MiddlewareError(
+            "requests are paused".to_string(),
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_middleware_runs_as_a_chain() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/orders").header("x-middleware", "outer");
+        then.status(200);
+    });
+    let calls = Arc::new(Mutex::new(Vec::new()));
+
+    let fetch = Fetch::builder()
+        .base_url(&server.base_url())
+        .middleware(Arc::new(Recorder {
+            name: "outer",
+            calls: calls.clone(),
+        }))
+        .middleware(Arc::new(Recorder {
+            name: "inner",
+            calls: calls.clone(),
+        }))
+        .build()?;
+    let res = fetch.get::<()>("/orders", no_body()).await?;
+
+    mock.assert_async().await;
+    assert_eq!(
+        vec![
+            "outer request",
+            "inner request",
+            "inner response",
+            "outer response"
+        ],
+        *calls.lock().unwrap()
+    );
+    // The outer middleware sees the response last
+    assert_eq!("outer", res.response_headers["x-seen-by"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_middleware_can_reject_requests() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+
+    let fetch = Fetch::builder()
+        .base_url(&server.base_url())
+        .middleware(Arc::new(Reject))
+        .build()?;
+
+    match fetch.get::<()>("/orders", no_body()).await {
+        Err(FetchError::MiddlewareError(message)) => assert_eq!("requests are paused", message),
+        other => panic!("expected a middleware error, got {other:?}"),
+    }
+    mock.assert_hits_async(0).await;
+    Ok(())
+}
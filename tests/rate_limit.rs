@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+use httpmock::prelude::*;
+use rust_fetch::{
+    Fetch, FetchConfig, FetchError, FetchOptions, RateLimitKey, RateLimitMode, RateLimiter,
+};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+fn limited_fetch(server: &MockServer, rate_limiter: RateLimiter) -> Fetch {
+    Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            rate_limiter: Some(rate_limiter),
+            ..Default::default()
+        }),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_requests_wait_for_budget() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/items");
+        then.status(200);
+    });
+
+    let fetch = limited_fetch(
+        &server,
+        RateLimiter::new(10, Duration::from_secs(1)).with_burst(1),
+    );
+    let started = Instant::now();
+    for _ in 0..3 {
+        fetch.get::<()>("/items", no_body()).await?;
+    }
+
+    mock.assert_hits_async(3).await;
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fail_fast_per_endpoint() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path_contains("/");
+        then.status(200);
+    });
+
+    let fetch = limited_fetch(
+        &server,
+        RateLimiter::new(1, Duration::from_secs(60))
+            .with_key(RateLimitKey::Endpoint(vec!["/search*".to_string()]))
+            .with_mode(RateLimitMode::FailFast),
+    );
+
+    fetch.get::<()>("/search?q=a", no_body()).await?;
+    match fetch.get::<()>("/search?q=b", no_body()).await {
+        Err(FetchError::RateLimited { key, retry_after }) => {
+            assert_eq!("/search*", key);
+            assert!(retry_after > Duration::from_secs(50));
+        }
+        other => panic!("expected a rate limit error, got {other:?}"),
+    }
+
+    // Endpoints outside the pattern are not limited
+    fetch.get::<()>("/health", no_body()).await?;
+    fetch.get::<()>("/health", no_body()).await?;
+    Ok(())
+}
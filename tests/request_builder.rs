@@ -0,0 +1,117 @@
+use std::{collections::HashMap, time::Duration};
+
+use httpmock::prelude::*;
+use rust_fetch::{reqwest::Method, Fetch, FetchError, IdempotencyKey, TimeoutPhase};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Order {
+    sku: String,
+    quantity: u32,
+}
+
+#[tokio::test]
+async fn test_builder_sends_query_headers_and_json() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/orders")
+            .query_param("dry_run", "true")
+            .header("x-tenant", "acme")
+            .header("idempotency-key", "order-1")
+            .header("content-type", "application/json")
+            .json_body(serde_json::json!({ "sku": "pen", "quantity": 2 }));
+        then.status(201)
+            .header("content-type", "application/json")
+            .json_body(serde_json::json!({ "sku": "pen", "quantity": 2 }));
+    });
+
+    let order = Order {
+        sku: "pen".to_string(),
+        quantity: 2,
+    };
+    let res = Fetch::new(&server.base_url(), None)?
+        .request(Method::POST, "/orders")
+        .query("dry_run", true)
+        .header("x-tenant", "acme")
+        .idempotency_key(IdempotencyKey::Key("order-1".to_string()))
+        .json(&order)
+        .send::<Order>()
+        .await?;
+
+    mock.assert_async().await;
+    assert_eq!(Some(order), res.body);
+    assert_eq!(Some("order-1".to_string()), res.idempotency_key);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_builder_sends_forms() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/orders/1")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body("sku=pen&quantity=3");
+        then.status(204);
+    });
+
+    let res = Fetch::new(&server.base_url(), None)?
+        .put_request("/orders/1")
+        .form(&Order {
+            sku: "pen".to_string(),
+            quantity: 3,
+        })
+        .deserialize_body(false)
+        .send::<()>()
+        .await?;
+
+    mock.assert_async().await;
+    assert_eq!(204, res.status);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_serialization_errors_surface_on_send() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/orders");
+        then.status(200);
+    });
+
+    let invalid: HashMap<(u8, u8), u8> = HashMap::from([((1, 2), 3)]);
+    let result = Fetch::new(&server.base_url(), None)?
+        .request(Method::POST, "/orders")
+        .json(&invalid)
+        .send::<()>()
+        .await;
+
+    assert!(matches!(result, Err(FetchError::SerializationError(_))));
+    mock.assert_hits_async(0).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sub_millisecond_timeout_rounds_up() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/orders");
+        then.status(200).delay(Duration::from_millis(200));
+    });
+
+    let res = Fetch::new(&server.base_url(), None)?
+        .get_request("/orders")
+        .timeout(Duration::from_micros(500))
+        .deserialize_body(false)
+        .send::<()>()
+        .await;
+
+    match res {
+        Err(FetchError::Timeout {
+            phase: TimeoutPhase::Request,
+            after,
+        }) => assert_eq!(Duration::from_millis(1), after),
+        other => panic!("expected a request timeout, got {other:?}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use httpmock::prelude::*;
+use rust_fetch::{Fetch, FetchConfig, FetchError, FetchOptions, RetryBudget, RetryPolicy};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+fn retrying_fetch(base_url: &str, retry: RetryPolicy) -> Fetch {
+    Fetch::new(
+        base_url,
+        Some(FetchConfig {
+            retry: Some(retry.with_backoff(Duration::from_millis(1), Duration::from_millis(10))),
+            ..Default::default()
+        }),
+    )
+    .unwrap()
+}
+
+/// Answers the first request with `503` and every later one with `200`. (The mock server cannot
+/// answer identical requests differently.)
+fn recovering_server() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { return };
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            let status = match counter.fetch_add(1, Ordering::SeqCst) {
+                0 => "503 Service Unavailable",
+                _ => "200 OK",
+            };
+            let _ = stream.write_all(
+                format!("HTTP/1.1 {status}\r\nconnection: close\r\ncontent-length: 0\r\n\r\n")
+                    .as_bytes(),
+            );
+        }
+    });
+    (base_url, requests)
+}
+
+#[tokio::test]
+async fn test_failed_get_is_retried() -> anyhow::Result<()> {
+    let (base_url, requests) = recovering_server();
+
+    let res = retrying_fetch(&base_url, RetryPolicy::new(2))
+        .get::<()>("/balance", no_body())
+        .await?;
+
+    assert_eq!(200, res.status);
+    assert_eq!(2, requests.load(Ordering::SeqCst));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retries_stop_at_max_retries_and_skip_post() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let get_mock = server.mock(|when, then| {
+        when.method(GET).path("/ledger");
+        then.status(503);
+    });
+    let post_mock = server.mock(|when, then| {
+        when.method(POST).path("/ledger");
+        then.status(503);
+    });
+
+    let fetch = retrying_fetch(&server.base_url(), RetryPolicy::new(2));
+    match fetch.get::<()>("/ledger", no_body()).await {
+        Err(FetchError::NetworkError(err)) => assert_eq!(503, err.status_code),
+        other => panic!("expected a 503, got {other:?}"),
+    }
+    assert!(fetch
+        .post::<(), _>("/ledger", Some(serde_json::json!({})), no_body())
+        .await
+        .is_err());
+
+    get_mock.assert_hits_async(3).await;
+    post_mock.assert_hits_async(1).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_exhausted_budget_stops_retries() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/ledger");
+        then.status(503);
+    });
+
+    let budget = RetryBudget::new(0.1).with_max_tokens(2);
+    let fetch = retrying_fetch(
+        &server.base_url(),
+        RetryPolicy::new(5).with_budget(budget.clone()),
+    );
+
+    match fetch.get::<()>("/ledger", no_body()).await {
+        Err(FetchError::RetryBudgetExhausted { attempts, source }) => {
+            assert_eq!(3, attempts);
+            assert!(matches!(*source, FetchError::NetworkError(_)));
+        }
+        other => panic!("expected an exhausted retry budget, got {other:?}"),
+    }
+    mock.assert_hits_async(3).await;
+    assert_eq!(0, budget.available());
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use httpmock::prelude::*;
+use rust_fetch::{
+    map_string, CacheStatus, DigestAuth, Fetch, FetchAuth, FetchConfig, FetchOptions, HttpCache,
+};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_tenant_handles_share_one_client() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let acme_mock = server.mock(|when, then| {
+        when.path("/api/v2/orders")
+            .header("x-tenant", "acme")
+            .header("x-request", "1");
+        then.status(200);
+    });
+    let globex_mock = server.mock(|when, then| {
+        when.path("/api/v2/orders").header("x-tenant", "globex");
+        then.status(200);
+    });
+    let root_mock = server.mock(|when, then| {
+        when.path("/api/orders").matches(|req| {
+            !req.headers
+                .iter()
+                .flatten()
+                .any(|(name, _)| name == "x-tenant")
+        });
+        then.status(200);
+    });
+
+    let fetch = Fetch::new(&format!("{}/api", server.base_url()), None)?;
+    let v2 = fetch.with_base_path("v2/");
+    let acme = v2.with_headers(map_string! { "x-tenant" => "acme" });
+    let globex = v2.with_headers(map_string! { "x-tenant" => "globex" });
+
+    acme.get::<()>(
+        "orders",
+        Some(FetchOptions {
+            headers: Some(map_string! { "x-request" => "1" }),
+            deserialize_body: false,
+            ..Default::default()
+        }),
+    )
+    .await?;
+    globex.get::<()>("/orders", no_body()).await?;
+    fetch.get::<()>("/orders", no_body()).await?;
+
+    acme_mock.assert_async().await;
+    globex_mock.assert_async().await;
+    root_mock.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_per_call_headers_override_scoped_headers() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/orders").header("x-tenant", "initech");
+        then.status(200);
+    });
+
+    let acme =
+        Fetch::new(&server.base_url(), None)?.with_headers(map_string! { "x-tenant" => "acme" });
+    acme.get::<()>(
+        "/orders",
+        Some(FetchOptions {
+            headers: Some(map_string! { "x-tenant" => "initech" }),
+            deserialize_body: false,
+            ..Default::default()
+        }),
+    )
+    .await?;
+
+    mock.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_with_auth_only_affects_the_derived_handle() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let challenge = server.mock(|when, then| {
+        when.path("/private").matches(|req| {
+            !req.headers
+                .iter()
+                .flatten()
+                .any(|(name, _)| name == "authorization")
+        });
+        then.status(401).header(
+            "www-authenticate",
+            r#"Digest realm="orders", qop="auth", nonce="abc", algorithm=SHA-256"#,
+        );
+    });
+    let authorized = server.mock(|when, then| {
+        when.path("/private").header_exists("authorization");
+        then.status(200);
+    });
+
+    let fetch = Fetch::new(&server.base_url(), None)?;
+    let admin = fetch.with_auth(FetchAuth::Digest(DigestAuth::new("admin", "secret")));
+
+    assert!(fetch.get::<()>("/private", no_body()).await.is_err());
+    admin.get::<()>("/private", no_body()).await?;
+
+    challenge.assert_hits_async(2).await;
+    authorized.assert_hits_async(1).await;
+    assert!(fetch.config.as_ref().unwrap().auth.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tenants_do_not_share_cached_responses() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    for tenant in ["acme", "globex"] {
+        server.mock(|when, then| {
+            when.path("/profile").header("x-tenant", tenant);
+            then.status(200)
+                .header("cache-control", "max-age=60")
+                .body(tenant);
+        });
+    }
+    let profile = server.mock(|when, then| {
+        when.path("/profile").matches(|req| {
+            !req.headers
+                .iter()
+                .flatten()
+                .any(|(name, _)| name == "x-tenant")
+        });
+        then.status(200).header("cache-control", "max-age=60");
+    });
+
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            cache: Some(HttpCache::memory(16)),
+            ..Default::default()
+        }),
+    )?;
+    let acme = fetch.with_headers(map_string! { "x-tenant" => "acme" });
+    let globex = fetch.with_headers(map_string! { "x-tenant" => "globex" });
+
+    let res = acme.get::<()>("/profile", no_body()).await?;
+    assert_eq!(b"acme", &res.raw_body.unwrap()[..]);
+    let res = globex.get::<()>("/profile", no_body()).await?;
+    assert_eq!(CacheStatus::Network, res.cache_status);
+    assert_eq!(b"globex", &res.raw_body.unwrap()[..]);
+    let res = acme.get::<()>("/profile", no_body()).await?;
+    assert_eq!(CacheStatus::Hit, res.cache_status);
+    assert_eq!(b"acme", &res.raw_body.unwrap()[..]);
+
+    // Handles authenticating as different users are kept apart as well
+    let alice = fetch.with_auth(FetchAuth::Digest(DigestAuth::new("alice", "secret")));
+    let bob = fetch.with_auth(FetchAuth::Digest(DigestAuth::new("bob", "secret")));
+    alice.get::<()>("/profile", no_body()).await?;
+    let res = bob.get::<()>("/profile", no_body()).await?;
+    assert_eq!(CacheStatus::Network, res.cache_status);
+    let res = alice.get::<()>("/profile", no_body()).await?;
+    assert_eq!(CacheStatus::Hit, res.cache_status);
+    profile.assert_hits_async(2).await;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::{sync::Arc, time::Duration};
+
+use httpmock::prelude::*;
+use rust_fetch::{AwsCredentials, Fetch, FetchConfig, FetchOptions, SigV4Signer};
+
+fn header(req: &HttpMockRequest, name: &str) -> Option<String> {
+    req.headers
+        .as_ref()?
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.clone())
+}
+
+#[tokio::test]
+async fn test_sigv4_signs_s3_put() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(PUT).path("/bucket/object.json").matches(|req| {
+            header(req, "authorization").is_some_and(|value| {
+                value.starts_with("AWS4-HMAC-SHA256 Credential=minioadmin/")
+                    && value.contains("/us-east-1/s3/aws4_request, SignedHeaders=content-type;host;x-amz-content-sha256;x-amz-date;x-amz-security-token, Signature=")
+            }) && header(req, "x-amz-content-sha256").as_deref()
+                // sha256 of `{"key":"value"}`
+                == Some("e43abcf3375244839c012f9633f95862d232a95b00d5bc7348b3098b9fed7f32")
+                && header(req, "x-amz-security-token").as_deref() == Some("session")
+        });
+        then.status(200);
+    });
+
+    let signer = SigV4Signer::new(
+        AwsCredentials::new("minioadmin", "minioadmin").with_session_token("session"),
+        "us-east-1",
+        "s3",
+    );
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            signer: Some(Arc::new(signer)),
+            ..Default::default()
+        }),
+    )?;
+
+    fetch
+        .put::<_, ()>(
+            "/bucket/object.json",
+            Some(serde_json::json!({ "key": "value" })),
+            Some(FetchOptions {
+                deserialize_body: false,
+                ..Default::default()
+            }),
+        )
+        .await?;
+
+    mock.assert_async().await;
+    Ok(())
+}
+
+#[test]
+fn test_sigv4_presign_put() -> anyhow::Result<()> {
+    let fetch = Fetch::new("http://localhost:9000", None)?;
+    let signer = SigV4Signer::new(
+        AwsCredentials::new("minioadmin", "minioadmin"),
+        "us-east-1",
+        "s3",
+    );
+
+    let url = signer.presign(
+        rust_fetch::reqwest::Method::PUT,
+        &fetch.build_url("/bucket/upload.bin", None)?,
+        Duration::from_secs(300),
+    )?;
+
+    let params: Vec<String> = url.query_pairs().map(|(key, _)| key.into_owned()).collect();
+    assert_eq!(
+        vec![
+            "X-Amz-Algorithm",
+            "X-Amz-Credential",
+            "X-Amz-Date",
+            "X-Amz-Expires",
+            "X-Amz-SignedHeaders",
+            "X-Amz-Signature"
+        ],
+        params
+    );
+    assert_eq!("/bucket/upload.bin", url.path());
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+
+use httpmock::prelude::*;
+use rust_fetch::{AdaptiveThrottle, Fetch, FetchConfig, FetchError, FetchOptions};
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+fn throttled_fetch(server: &MockServer, throttle: &AdaptiveThrottle) -> Fetch {
+    Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            throttle: Some(throttle.clone()),
+            ..Default::default()
+        }),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_quota_is_tracked_per_host() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/repos");
+        then.status(200)
+            .header("x-ratelimit-limit", "5000")
+            .header("x-ratelimit-remaining", "4999")
+            .header("x-ratelimit-reset", "3600");
+    });
+
+    let throttle = AdaptiveThrottle::new();
+    throttled_fetch(&server, &throttle)
+        .get::<()>("/repos", no_body())
+        .await?;
+
+    let quota = throttle.quota("127.0.0.1").unwrap();
+    assert_eq!(Some(5000), quota.limit);
+    assert_eq!(4999, quota.remaining);
+    assert_eq!(1, throttle.quotas().len());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_exhausted_quota_waits_for_reset() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/search");
+        then.status(200)
+            .header("ratelimit", "\"default\";r=0;t=1")
+            .header("ratelimit-policy", "\"default\";q=10;w=1");
+    });
+
+    let throttle = AdaptiveThrottle::new();
+    let fetch = throttled_fetch(&server, &throttle);
+    fetch.get::<()>("/search", no_body()).await?;
+
+    let started = Instant::now();
+    fetch.get::<()>("/search", no_body()).await?;
+
+    mock.assert_hits_async(2).await;
+    assert!(started.elapsed() >= Duration::from_millis(900));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retry_after_beyond_max_wait_fails_fast() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/export");
+        then.status(429).header("retry-after", "120");
+    });
+
+    let throttle = AdaptiveThrottle::new().with_max_wait(Duration::from_secs(10));
+    let fetch = throttled_fetch(&server, &throttle);
+    assert!(fetch.get::<()>("/export", no_body()).await.is_err());
+
+    match fetch.get::<()>("/export", no_body()).await {
+        Err(FetchError::RateLimited { key, retry_after }) => {
+            assert_eq!("127.0.0.1", key);
+            assert!(retry_after > Duration::from_secs(100));
+        }
+        other => panic!("expected a rate limit error, got {other:?}"),
+    }
+    mock.assert_hits_async(1).await;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    time::Duration,
+};
+
+use httpmock::prelude::*;
+use rust_fetch::{Fetch, FetchConfig, FetchError, FetchOptions, RetryPolicy, TimeoutPhase};
+
+/// Answers every request with `head`, then stalls for a second before closing the connection
+fn stalling_server(head: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { return };
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(head.as_bytes());
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    });
+    base_url
+}
+
+fn options(timeout_ms: Option<u64>, deadline_ms: Option<u64>) -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        timeout_ms,
+        deadline_ms,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_per_call_timeout_overrides_config() -> anyhow::Result<()> {
+    let base_url = stalling_server("");
+    let fetch = Fetch::new(
+        &base_url,
+        Some(FetchConfig {
+            timeout_ms: Some(5_000),
+            ..Default::default()
+        }),
+    )?;
+
+    match fetch.get::<()>("/slow", options(Some(100), None)).await {
+        Err(FetchError::Timeout { phase, after }) => {
+            assert_eq!(TimeoutPhase::Request, phase);
+            assert_eq!(Duration::from_millis(100), after);
+        }
+        other => panic!("expected a timeout, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stalled_body_is_a_read_timeout() -> anyhow::Result<()> {
+    let base_url = stalling_server("HTTP/1.1 200 OK\r\ncontent-length: 10\r\n\r\nab");
+    let fetch = Fetch::new(
+        &base_url,
+        Some(FetchConfig {
+            timeout_ms: Some(5_000),
+            read_timeout_ms: Some(100),
+            ..Default::default()
+        }),
+    )?;
+
+    match fetch.get::<()>("/download", options(None, None)).await {
+        Err(FetchError::Timeout { phase, after }) => {
+            assert_eq!(TimeoutPhase::Read, phase);
+            assert_eq!(Duration::from_millis(100), after);
+        }
+        other => panic!("expected a timeout, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deadline_covers_retries() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/flaky");
+        then.status(503).delay(Duration::from_millis(60));
+    });
+    let fetch = Fetch::new(
+        &server.base_url(),
+        Some(FetchConfig {
+            retry: Some(
+                RetryPolicy::new(10)
+                    .with_backoff(Duration::from_millis(1), Duration::from_millis(1)),
+            ),
+            deadline_ms: Some(5_000),
+            ..Default::default()
+        }),
+    )?;
+
+    match fetch.get::<()>("/flaky", options(None, Some(150))).await {
+        Err(FetchError::Timeout { phase, after }) => {
+            assert_eq!(TimeoutPhase::Deadline, phase);
+            assert_eq!(Duration::from_millis(150), after);
+        }
+        other => panic!("expected the deadline to expire, got {other:?}"),
+    }
+    assert!(mock.hits_async().await < 10);
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use httpmock::prelude::*;
+use rust_fetch::{Fetch, FetchError, FetchOptions, ResourceVersion};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Account {
+    owner: String,
+    balance: i64,
+}
+
+fn no_body() -> Option<FetchOptions> {
+    Some(FetchOptions {
+        deserialize_body: false,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_put_versioned_sends_if_match() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/accounts/1");
+        then.status(200)
+            .header("etag", "\"7\"")
+            .json_body(serde_json::json!({ "owner": "ada", "balance": 10 }));
+    });
+    let put_mock = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/accounts/1")
+            .header("if-match", "\"7\"")
+            .json_body(serde_json::json!({ "owner": "ada", "balance": 25 }));
+        then.status(204).header("etag", "\"8\"");
+    });
+
+    let fetch = Fetch::new(&server.base_url(), None)?;
+    let mut account = fetch.get_versioned::<Account>("/accounts/1", None).await?;
+    assert_eq!(Some("\"7\"".to_string()), account.version.etag);
+
+    account.value.balance += 15;
+    let res = fetch
+        .put_versioned::<_, ()>("/accounts/1", &account, no_body())
+        .await?;
+
+    put_mock.assert_async().await;
+    assert_eq!(Some("\"8\"".to_string()), res.version().etag);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_precondition_failed_is_a_typed_conflict() -> anyhow::Result<()> {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(httpmock::Method::PATCH)
+            .path("/accounts/1")
+            .header("if-unmodified-since", "Sun, 11 Oct 2026 00:00:00 GMT");
+        then.status(412)
+            .header("etag", "\"9\"")
+            .body("modified by someone else");
+    });
+
+    let fetch = Fetch::new(&server.base_url(), None)?;
+    let version = ResourceVersion {
+        etag: None,
+        last_modified: Some("Sun, 11 Oct 2026 00:00:00 GMT".to_string()),
+    };
+
+    let res = fetch
+        .patch_versioned::<_, ()>(
+            "/accounts/1",
+            &version,
+            Some(serde_json::json!({ "balance": 0 })),
+            no_body(),
+        )
+        .await;
+
+    match res {
+        Err(FetchError::PreconditionFailed { etag, error }) => {
+            assert_eq!(Some("\"9\"".to_string()), etag);
+            assert_eq!(412, error.status_code);
+        }
+        other => panic!("expected a precondition failure, got {other:?}"),
+    }
+    Ok(())
+}